@@ -0,0 +1,200 @@
+//! Append-only audit log of every change to the registry: who made it
+//! (identity, how they authenticated, source address, MCP session and
+//! client), the operation, and which fields of which entity changed.
+//! Entries are JSON Lines in `AUDIT_LOG_FILE`, one per changed entity.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use actix_web::dev::Payload;
+use actix_web::http::header;
+use actix_web::{web, FromRequest, HttpRequest};
+use serde::{Deserialize, Serialize};
+
+use crate::auth::{AuthMethod, Principal};
+use crate::config::Config;
+use crate::error::ApiError;
+use crate::model::{ChangeAction, ChangeEvent, EntityKind};
+
+/// Who makes a change, and from where.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Actor {
+    /// The principal's name: API key name or JWT subject.
+    #[serde(rename = "actor")]
+    pub name: String,
+    pub auth_method: AuthMethod,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_ip: Option<String>,
+    /// Id of the MCP session the change was made in.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mcp_session: Option<String>,
+    /// `clientInfo.name` of that session.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mcp_client: Option<String>,
+    /// Whose permissions the change is checked against.
+    #[serde(skip)]
+    pub principal: Principal,
+}
+
+impl Actor {
+    /// A caller outside of any MCP session.
+    pub fn new(principal: &Principal, source_ip: Option<String>) -> Self {
+        Self {
+            name: principal.name.clone(),
+            auth_method: principal.method,
+            source_ip,
+            mcp_session: None,
+            mcp_client: None,
+            principal: principal.clone(),
+        }
+    }
+}
+
+/// The client address of `req`, without the port. This is the peer's
+/// address unless the peer is one of `TRUSTED_PROXIES`: then the
+/// `Forwarded`/`X-Forwarded-For` chain is followed back from the nearest
+/// hop for as long as each hop is itself a trusted proxy, so a client
+/// cannot forge the address by sending the headers itself.
+pub fn source_ip(req: &HttpRequest) -> Option<String> {
+    let mut client = req.peer_addr()?.ip();
+    let trusted = req.app_data::<web::Data<Config>>().map(|c| c.trusted_proxies.as_slice()).unwrap_or_default();
+    for hop in forwarded_for(req).iter().rev() {
+        if !trusted.contains(&client) {
+            break;
+        }
+        match parse_ip(hop) {
+            Some(ip) => client = ip,
+            None => break,
+        }
+    }
+    Some(client.to_string())
+}
+
+/// The client addresses proxies recorded in `Forwarded` (`for=`) or, if
+/// absent, `X-Forwarded-For`, the nearest hop last.
+fn forwarded_for(req: &HttpRequest) -> Vec<String> {
+    let values = |name| req.headers().get_all(name).filter_map(|v| v.to_str().ok()).flat_map(|v| v.split(','));
+    let forwarded: Vec<String> = values(header::FORWARDED)
+        .filter_map(|element| {
+            element.split(';').find_map(|pair| {
+                let (key, value) = pair.trim().split_once('=')?;
+                key.eq_ignore_ascii_case("for").then(|| value.trim_matches('"').to_string())
+            })
+        })
+        .collect();
+    if !forwarded.is_empty() {
+        return forwarded;
+    }
+    values(header::X_FORWARDED_FOR).map(|v| v.trim().to_string()).collect()
+}
+
+/// An address as proxies write it: `ip`, `ip:port` or `[ipv6]:port`.
+fn parse_ip(hop: &str) -> Option<IpAddr> {
+    hop.parse::<SocketAddr>()
+        .map(|socket| socket.ip())
+        .or_else(|_| hop.trim_start_matches('[').trim_end_matches(']').parse())
+        .ok()
+}
+
+impl FromRequest for Actor {
+    type Error = ApiError;
+    type Future = std::future::Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let actor = Principal::from_request(req, payload)
+            .into_inner()
+            .map(|principal| Actor::new(&principal, source_ip(req)));
+        std::future::ready(actor)
+    }
+}
+
+/// The request that made a change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Operation {
+    Upsert,
+    Patch,
+    Delete,
+    Batch,
+    ReplaceDatasource,
+}
+
+/// One line of the audit log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// RFC 3339 time of the change, in UTC.
+    pub timestamp: String,
+    #[serde(flatten)]
+    pub actor: Actor,
+    pub operation: Operation,
+    pub kind: EntityKind,
+    pub key: String,
+    pub action: ChangeAction,
+    /// Top-level fields that differ before and after the change.
+    pub changed_fields: Vec<String>,
+}
+
+impl AuditEntry {
+    pub fn time(&self) -> Option<SystemTime> {
+        humantime::parse_rfc3339(&self.timestamp).ok()
+    }
+}
+
+pub struct AuditLog {
+    path: PathBuf,
+    file: Mutex<File>,
+}
+
+impl AuditLog {
+    /// Opens `path` for appending, creating it if needed.
+    pub fn open(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        Ok(Self {
+            path,
+            file: Mutex::new(file),
+        })
+    }
+
+    /// Appends an entry per event. A failed write is reported but does not
+    /// undo the change, which is already saved.
+    pub fn record(&self, actor: &Actor, operation: Operation, events: &[ChangeEvent]) {
+        let timestamp = humantime::format_rfc3339_millis(SystemTime::now()).to_string();
+        let mut lines = String::new();
+        for event in events {
+            let entry = AuditEntry {
+                timestamp: timestamp.clone(),
+                actor: actor.clone(),
+                operation,
+                kind: event.kind,
+                key: event.key.clone(),
+                action: event.action,
+                changed_fields: event.changed_fields(),
+            };
+            if let Ok(line) = serde_json::to_string(&entry) {
+                lines.push_str(&line);
+                lines.push('\n');
+            }
+        }
+        let mut file = self.file.lock().unwrap();
+        if let Err(e) = file.write_all(lines.as_bytes()).and_then(|()| file.flush()) {
+            eprintln!("Failed to write audit log {}: {e}", self.path.display());
+        }
+    }
+
+    /// Every entry, oldest first. Lines that don't parse are skipped.
+    pub fn entries(&self) -> io::Result<Vec<AuditEntry>> {
+        let file = File::open(&self.path)?;
+        let mut entries = Vec::new();
+        for line in BufReader::new(file).lines() {
+            if let Ok(entry) = serde_json::from_str(&line?) {
+                entries.push(entry);
+            }
+        }
+        Ok(entries)
+    }
+}
@@ -0,0 +1,212 @@
+//! Bearer JWTs from an external identity provider. Signatures are checked
+//! against a JWKS read from `JWT_JWKS_FILE` or fetched from `JWT_JWKS_URL`
+//! (and refetched periodically, so rotated keys are picked up); `iss`,
+//! `aud` and `exp` must match. Roles from the token map to scopes, and
+//! teams from it grant membership of those teams.
+
+use std::collections::HashMap;
+use std::io;
+use std::sync::RwLock;
+use std::time::Duration;
+
+use actix_web::web;
+use jsonwebtoken::jwk::{AlgorithmParameters, JwkSet};
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+
+use super::{AuthMethod, Principal, Scope};
+use crate::config::Config;
+
+enum JwksSource {
+    File(String),
+    Url(String),
+}
+
+impl JwksSource {
+    fn load(&self) -> io::Result<JwkSet> {
+        let invalid = |source: &str, e: String| io::Error::new(io::ErrorKind::InvalidData, format!("{source}: {e}"));
+        match self {
+            JwksSource::File(path) => {
+                let content = std::fs::read_to_string(path)?;
+                serde_json::from_str(&content).map_err(|e| invalid(path, e.to_string()))
+            }
+            JwksSource::Url(url) => ureq::get(url)
+                .call()
+                .map_err(|e| invalid(url, e.to_string()))?
+                .into_json()
+                .map_err(|e| invalid(url, e.to_string())),
+        }
+    }
+}
+
+pub struct JwtConfig {
+    source: JwksSource,
+    keys: RwLock<JwkSet>,
+    /// How often a `JWT_JWKS_URL` is refetched.
+    refresh_interval: Duration,
+    issuer: String,
+    /// Required in the `aud` claim; when it is a URL it is also advertised
+    /// as the protected resource.
+    pub audience: String,
+    /// Dotted path of the claim holding the caller's roles, e.g.
+    /// `realm_access.roles`.
+    roles_claim: String,
+    /// Dotted path of the claim listing the teams the caller belongs to;
+    /// each grants `workspace:<team>`.
+    teams_claim: Option<String>,
+    /// Scopes granted for identity-provider roles. Roles that are not
+    /// mapped but name a scope, e.g. `write`, grant that scope.
+    role_scopes: HashMap<String, Scope>,
+    /// Advertised in the protected resource metadata.
+    pub authorization_servers: Vec<String>,
+}
+
+impl JwtConfig {
+    /// Reads the JWT settings through `var` (see `Config::from_vars`), or
+    /// `None` if neither `JWT_JWKS_FILE` nor `JWT_JWKS_URL` is set.
+    pub fn from_vars(var: impl Fn(&str) -> Option<String>) -> io::Result<Option<Self>> {
+        let source = match (var("JWT_JWKS_FILE"), var("JWT_JWKS_URL")) {
+            (Some(path), _) => JwksSource::File(path),
+            (_, Some(url)) => JwksSource::Url(url),
+            _ => return Ok(None),
+        };
+        let required = |name: &str| {
+            var(name).ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidInput, format!("{name} is required when JWT validation is enabled"))
+            })
+        };
+        let issuer = required("JWT_ISSUER")?;
+        let audience = required("JWT_AUDIENCE")?;
+        let roles_claim = var("JWT_ROLES_CLAIM").unwrap_or_else(|| "roles".to_string());
+        let teams_claim = var("JWT_TEAMS_CLAIM");
+        let role_scopes = match var("JWT_ROLE_SCOPES") {
+            Some(mapping) => parse_role_scopes(&mapping)?,
+            None => HashMap::new(),
+        };
+        let authorization_servers = match var("JWT_AUTHORIZATION_SERVERS") {
+            Some(servers) => servers.split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect(),
+            None => vec![issuer.clone()],
+        };
+        let refresh_interval = match crate::config::env_number(&var, "JWT_JWKS_REFRESH_SECS", 300)? {
+            0 => return Err(io::Error::new(io::ErrorKind::InvalidInput, "JWT_JWKS_REFRESH_SECS must be at least 1")),
+            secs => Duration::from_secs(secs),
+        };
+        let keys = RwLock::new(source.load()?);
+        Ok(Some(Self {
+            source,
+            keys,
+            refresh_interval,
+            issuer,
+            audience,
+            roles_claim,
+            teams_claim,
+            role_scopes,
+            authorization_servers,
+        }))
+    }
+
+    /// Validates `token` and returns the caller it identifies, named after
+    /// its `sub` claim. `Err` says why the token was rejected.
+    pub fn verify(&self, token: &str) -> Result<Principal, String> {
+        let header = jsonwebtoken::decode_header(token).map_err(|e| format!("Invalid token: {e}"))?;
+        let key = {
+            let keys = self.keys.read().unwrap();
+            let jwk = match &header.kid {
+                Some(kid) => keys.find(kid),
+                // Without a key id, only an unambiguous key set works.
+                None if keys.keys.len() == 1 => keys.keys.first(),
+                None => None,
+            }
+            .ok_or("Token was not signed by a known key")?;
+            if matches!(jwk.algorithm, AlgorithmParameters::OctetKey(_)) {
+                return Err("Symmetric keys are not accepted".to_string());
+            }
+            if jwk.common.key_algorithm.is_some_and(|alg| alg.to_string().parse::<Algorithm>().ok() != Some(header.alg)) {
+                return Err("Token algorithm does not match its key".to_string());
+            }
+            DecodingKey::from_jwk(jwk).map_err(|e| format!("Unusable key: {e}"))?
+        };
+        if matches!(header.alg, Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512) {
+            return Err("Symmetric algorithms are not accepted".to_string());
+        }
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[&self.issuer]);
+        validation.set_audience(&[&self.audience]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+        let claims = jsonwebtoken::decode::<serde_json::Value>(token, &key, &validation)
+            .map_err(|e| format!("Invalid token: {e}"))?
+            .claims;
+
+        let name = claims["sub"].as_str().unwrap_or_default().to_string();
+        let mut scopes: Vec<Scope> = claim_values(&claims, &self.roles_claim)
+            .filter_map(|role| self.scope_for(role))
+            .collect();
+        if let Some(teams_claim) = &self.teams_claim {
+            scopes.extend(claim_values(&claims, teams_claim).map(|team| Scope::Workspace(team.to_string())));
+        }
+        Ok(Principal {
+            name,
+            method: AuthMethod::Jwt,
+            scopes,
+        })
+    }
+
+    fn scope_for(&self, role: &str) -> Option<Scope> {
+        match self.role_scopes.get(role) {
+            Some(scope) => Some(scope.clone()),
+            None => Scope::try_from(role.to_string()).ok(),
+        }
+    }
+}
+
+/// The values of the claim at dotted `path`: a JSON array of strings, or a
+/// space-separated string like OAuth's `scope`.
+fn claim_values<'a>(claims: &'a serde_json::Value, path: &str) -> Box<dyn Iterator<Item = &'a str> + 'a> {
+    match path.split('.').try_fold(claims, |value, segment| value.get(segment)) {
+        Some(serde_json::Value::Array(values)) => Box::new(values.iter().filter_map(|v| v.as_str())),
+        Some(serde_json::Value::String(values)) => Box::new(values.split_whitespace()),
+        _ => Box::new(std::iter::empty()),
+    }
+}
+
+/// Parses `JWT_ROLE_SCOPES`, e.g. `registry-admins=admin,trading-devs=workspace:trading`.
+fn parse_role_scopes(mapping: &str) -> io::Result<HashMap<String, Scope>> {
+    mapping
+        .split(',')
+        .filter(|pair| !pair.trim().is_empty())
+        .map(|pair| {
+            let invalid = |e: String| io::Error::new(io::ErrorKind::InvalidInput, format!("JWT_ROLE_SCOPES: {e}"));
+            let (role, scope) = pair.split_once('=').ok_or_else(|| invalid(format!("expected role=scope, got '{pair}'")))?;
+            let scope = Scope::try_from(scope.trim().to_string()).map_err(invalid)?;
+            Ok((role.trim().to_string(), scope))
+        })
+        .collect()
+}
+
+/// Refetches a `JWT_JWKS_URL` in the background so rotated signing keys are
+/// accepted without a restart. A failed fetch keeps the previous keys.
+pub fn spawn_jwks_refresher(config: web::Data<Config>) {
+    let Some(jwt) = &config.jwt else { return };
+    if !matches!(jwt.source, JwksSource::Url(_)) {
+        return;
+    }
+    let period = jwt.refresh_interval;
+    actix_web::rt::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            let config = web::Data::clone(&config);
+            let result = tokio::task::spawn_blocking(move || {
+                let Some(jwt) = &config.jwt else { return Ok(()) };
+                let keys = jwt.source.load()?;
+                *jwt.keys.write().unwrap() = keys;
+                Ok::<_, io::Error>(())
+            })
+            .await;
+            if let Ok(Err(e)) = result {
+                eprintln!("Failed to refresh JWKS: {e}");
+            }
+        }
+    });
+}
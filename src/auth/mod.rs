@@ -0,0 +1,413 @@
+//! Authentication and scopes. Callers present an API key from the JSON
+//! file referenced by `API_KEYS_FILE` (stored there as SHA-256 hashes) as
+//! `Authorization: Bearer <key>` or `X-API-Key: <key>`, or a bearer JWT
+//! validated by `jwt`. Without either configured every request is
+//! allowed, as before authentication existed.
+
+pub mod jwt;
+
+use std::collections::HashMap;
+
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{Payload, ServiceRequest, ServiceResponse};
+use actix_web::http::{header, Method};
+use actix_web::middleware::Next;
+use actix_web::{web, FromRequest, HttpMessage, HttpRequest, ResponseError};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::config::Config;
+use crate::error::{ApiError, ErrorCode};
+use crate::handlers::public_url;
+use crate::model::Entity;
+
+/// A permission granted to an API key.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum Scope {
+    /// Read every entity; use MCP read tools.
+    Read,
+    /// Read everything; create entities and change unowned ones.
+    Write,
+    /// Everything, including changing any team's entities, replacing the
+    /// whole datasource and `/api/admin`.
+    Admin,
+    /// Membership of a team: read everything, and change the entities the
+    /// team owns.
+    Workspace(String),
+}
+
+impl TryFrom<String> for Scope {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "read" => Ok(Scope::Read),
+            "write" => Ok(Scope::Write),
+            "admin" => Ok(Scope::Admin),
+            _ => match value.strip_prefix("workspace:") {
+                Some(team) if !team.is_empty() => Ok(Scope::Workspace(team.to_string())),
+                _ => Err(format!(
+                    "unknown scope '{value}', expected read, write, admin or workspace:<team>"
+                )),
+            },
+        }
+    }
+}
+
+/// One entry of `API_KEYS_FILE`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiKey {
+    /// Identifies the caller, e.g. in logs.
+    pub name: String,
+    /// Hex-encoded SHA-256 of the key, e.g. from `printf %s "$KEY" | sha256sum`.
+    pub key_sha256: String,
+    pub scopes: Vec<Scope>,
+}
+
+/// The configured keys, indexed by hash.
+pub struct ApiKeys {
+    by_hash: HashMap<String, Principal>,
+}
+
+impl ApiKeys {
+    pub fn new(keys: Vec<ApiKey>) -> Self {
+        let by_hash = keys
+            .into_iter()
+            .map(|k| {
+                let principal = Principal {
+                    name: k.name,
+                    method: AuthMethod::ApiKey,
+                    scopes: k.scopes,
+                };
+                (k.key_sha256.to_ascii_lowercase(), principal)
+            })
+            .collect();
+        Self { by_hash }
+    }
+
+    pub fn find(&self, key: &str) -> Option<&Principal> {
+        self.by_hash.get(&hash_key(key))
+    }
+}
+
+pub fn hash_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+/// How a caller proved its identity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthMethod {
+    /// Authentication is disabled, or the caller is local (stdio).
+    None,
+    ApiKey,
+    Jwt,
+}
+
+/// The authenticated caller of a request.
+#[derive(Debug, Clone)]
+pub struct Principal {
+    pub name: String,
+    pub method: AuthMethod,
+    scopes: Vec<Scope>,
+}
+
+impl Default for Principal {
+    /// A caller without scopes, which may do nothing.
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            method: AuthMethod::None,
+            scopes: Vec::new(),
+        }
+    }
+}
+
+impl Principal {
+    /// The caller when authentication is disabled.
+    pub fn anonymous() -> Self {
+        Self {
+            name: "anonymous".to_string(),
+            method: AuthMethod::None,
+            scopes: vec![Scope::Admin],
+        }
+    }
+
+    fn has(&self, scope: &Scope) -> bool {
+        self.scopes.contains(scope)
+    }
+
+    /// Whether `other` identifies the same caller, whatever its scopes.
+    pub fn is_same_caller(&self, other: &Principal) -> bool {
+        self.name == other.name && self.method == other.method
+    }
+
+    pub fn is_admin(&self) -> bool {
+        self.has(&Scope::Admin)
+    }
+
+    /// May create entities and change unowned ones.
+    pub fn can_write(&self) -> bool {
+        self.is_admin() || self.has(&Scope::Write)
+    }
+
+    pub fn can_read(&self) -> bool {
+        self.can_write() || !self.scopes.is_empty()
+    }
+
+    /// The teams the caller is a member of.
+    pub fn teams(&self) -> impl Iterator<Item = &str> {
+        self.scopes.iter().filter_map(|s| match s {
+            Scope::Workspace(team) => Some(team.as_str()),
+            _ => None,
+        })
+    }
+
+    pub fn is_member(&self, team: &str) -> bool {
+        self.teams().any(|t| t == team)
+    }
+
+    /// Fails with 403 unless the caller has the `admin` scope. Admin
+    /// handlers check this themselves as well as relying on the middleware.
+    pub fn require_admin(&self) -> Result<(), ApiError> {
+        if self.is_admin() {
+            Ok(())
+        } else {
+            Err(ApiError::new(
+                ErrorCode::Forbidden,
+                format!("'{}' lacks the 'admin' scope", self.name),
+            ))
+        }
+    }
+
+    /// May change at least some entities: unowned ones, or its teams'.
+    pub fn may_write(&self) -> bool {
+        self.can_write() || self.teams().next().is_some()
+    }
+
+    /// Checks a change of an entity from `before` to `after` (`None` for an
+    /// entity that does not exist yet, or is being deleted). An existing
+    /// entity may be changed by members of a team owning it, or, if it is
+    /// unowned, with the `write` scope. What it becomes must be allowed as
+    /// well: with the `write` scope or by a member of a team that will own
+    /// it, so a team cannot hand its entity to other teams. Admins may
+    /// change anything.
+    pub fn authorize<T: Entity>(&self, before: Option<&T>, after: Option<&T>) -> Result<(), ApiError> {
+        if self.is_admin() {
+            return Ok(());
+        }
+        let forbidden = |detail: String| Err(ApiError::new(ErrorCode::Forbidden, detail));
+        let label = T::KIND.label();
+        if let Some(entity) = before {
+            let owners = entity.owning_teams();
+            if owners.is_empty() && !self.can_write() {
+                return forbidden(format!(
+                    "{label} '{}' has no owners; changing it needs the 'write' scope",
+                    entity.key()
+                ));
+            }
+            if !owners.is_empty() && !owners.iter().any(|t| self.is_member(t)) {
+                return forbidden(format!(
+                    "{label} '{}' is owned by {}; '{}' is not a member",
+                    entity.key(),
+                    owners.join(", "),
+                    self.name
+                ));
+            }
+        }
+        if let Some(entity) = after {
+            let owners = entity.owning_teams();
+            if !self.can_write() && !owners.iter().any(|t| self.is_member(t)) {
+                return forbidden(match (before, owners.is_empty()) {
+                    (None, _) => format!(
+                        "Creating {} '{}' needs the 'write' scope or membership of a team it is owned by",
+                        label.to_lowercase(),
+                        entity.key()
+                    ),
+                    (Some(_), true) => format!(
+                        "{label} '{}' would have no owners; that needs the 'write' scope",
+                        entity.key()
+                    ),
+                    (Some(_), false) => format!(
+                        "{label} '{}' would be owned by {}; '{}' is not a member",
+                        entity.key(),
+                        owners.join(", "),
+                        self.name
+                    ),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromRequest for Principal {
+    type Error = ApiError;
+    type Future = std::future::Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let principal = req
+            .extensions()
+            .get::<Principal>()
+            .cloned()
+            .ok_or_else(|| ApiError::new(ErrorCode::Unauthorized, "Request was not authenticated"));
+        std::future::ready(principal)
+    }
+}
+
+/// What a route requires of the caller.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Access {
+    /// Discovery documents clients fetch before authenticating, and
+    /// health probes.
+    Public,
+    Read,
+    /// `write` or a team membership; handlers check ownership per entity.
+    Write,
+    Admin,
+}
+
+impl Access {
+    fn scope(self) -> &'static str {
+        match self {
+            Access::Public | Access::Read => "read",
+            Access::Write => "write",
+            Access::Admin => "admin",
+        }
+    }
+}
+
+/// The route pattern `req` resolves to, e.g. `/api/services/{name}`, or
+/// else its path. Both are taken from the percent-decoded path the router
+/// matches on, so `/api/%64atasource` is `/api/datasource`.
+pub fn route_path(req: &HttpRequest) -> String {
+    let path = req.match_info().as_str();
+    req.resource_map().match_pattern(path).unwrap_or_else(|| path.to_string())
+}
+
+/// `path` is the route pattern or decoded path from `route_path`.
+fn required_access(method: &Method, path: &str) -> Access {
+    let read_only = matches!(*method, Method::GET | Method::HEAD);
+    if path.starts_with("/.well-known/") || path == "/healthz" || path == "/readyz" {
+        Access::Public
+    } else if path.starts_with("/api/admin/")
+        || path == "/api/audit"
+        || path.starts_with("/api/webhooks")
+        || (path == "/api/datasource" && !read_only)
+    {
+        Access::Admin
+    } else if read_only || !path.starts_with("/api/") {
+        // MCP transports only need read; write tools check ownership.
+        Access::Read
+    } else {
+        Access::Write
+    }
+}
+
+fn allows(principal: &Principal, access: Access) -> bool {
+    match access {
+        Access::Public | Access::Read => principal.can_read(),
+        Access::Write => principal.may_write(),
+        Access::Admin => principal.is_admin(),
+    }
+}
+
+/// The bearer token presented with a request, if any.
+fn bearer_token(req: &ServiceRequest) -> Option<&str> {
+    req.headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .map(str::trim)
+}
+
+/// Identifies the caller from an `X-API-Key` header, or a bearer token
+/// that is a JWT (three dot-separated segments) or an API key.
+fn identify(req: &ServiceRequest, config: &Config) -> Result<Principal, ApiError> {
+    if !config.auth_enabled() {
+        return Ok(Principal::anonymous());
+    }
+    let unauthorized = |detail: &str| ApiError::new(ErrorCode::Unauthorized, detail);
+    let api_key = req.headers().get("x-api-key").and_then(|h| h.to_str().ok()).map(str::trim);
+    let (token, is_jwt) = match (api_key, bearer_token(req)) {
+        (Some(key), _) => (key, false),
+        (None, Some(token)) => (token, token.split('.').count() == 3),
+        (None, None) => return Err(unauthorized("Missing API key or bearer token")),
+    };
+    match (&config.jwt, &config.api_keys) {
+        (Some(jwt), _) if is_jwt => jwt.verify(token).map_err(|e| unauthorized(&e)),
+        (_, Some(keys)) if !is_jwt => keys.find(token).cloned().ok_or_else(|| unauthorized("Invalid API key")),
+        _ if is_jwt => Err(unauthorized("Bearer JWTs are not accepted; send an API key")),
+        _ => Err(unauthorized("API keys are not accepted; send a bearer JWT")),
+    }
+}
+
+/// The `WWW-Authenticate` challenge for a rejected request. With JWTs it
+/// points MCP clients at the protected resource metadata.
+fn challenge(req: &ServiceRequest, config: &Config, err: &ApiError, access: Access) -> String {
+    let mut params = Vec::new();
+    if config.jwt.is_some() {
+        let metadata = public_url(req.request(), config, "/.well-known/oauth-protected-resource");
+        params.push(format!("resource_metadata=\"{metadata}\""));
+    }
+    match err.code {
+        ErrorCode::Forbidden => {
+            params.push("error=\"insufficient_scope\"".to_string());
+            params.push(format!("scope=\"{}\"", access.scope()));
+        }
+        _ if bearer_token(req).is_some() => params.push("error=\"invalid_token\"".to_string()),
+        _ => {}
+    }
+    if params.is_empty() {
+        "Bearer".to_string()
+    } else {
+        format!("Bearer {}", params.join(", "))
+    }
+}
+
+/// Middleware that authenticates every request and checks the route's
+/// required scope. Handlers extract the caller as a `Principal`.
+pub async fn authenticate(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, actix_web::Error> {
+    let config = req
+        .app_data::<web::Data<Config>>()
+        .cloned()
+        .expect("Config must be registered as app data");
+    let access = required_access(req.method(), &route_path(req.request()));
+    if access == Access::Public {
+        return next.call(req).await.map(ServiceResponse::map_into_left_body);
+    }
+    let result = identify(&req, &config).and_then(|principal| {
+        if allows(&principal, access) {
+            Ok(principal)
+        } else {
+            Err(ApiError::new(
+                ErrorCode::Forbidden,
+                format!(
+                    "'{}' lacks the '{}' scope for {} {}",
+                    principal.name,
+                    access.scope(),
+                    req.method(),
+                    req.path()
+                ),
+            ))
+        }
+    });
+
+    match result {
+        Ok(principal) => {
+            req.extensions_mut().insert(principal);
+            next.call(req).await.map(ServiceResponse::map_into_left_body)
+        }
+        Err(err) => {
+            let mut response = err.error_response();
+            if let Ok(value) = header::HeaderValue::from_str(&challenge(&req, &config, &err, access)) {
+                response.headers_mut().insert(header::WWW_AUTHENTICATE, value);
+            }
+            Ok(req.into_response(response).map_into_right_body())
+        }
+    }
+}
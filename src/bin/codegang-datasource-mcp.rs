@@ -0,0 +1,258 @@
+//! MCP server over stdio for local agents.
+//!
+//! Reads newline-delimited JSON-RPC messages from stdin and writes responses
+//! to stdout, using the same dispatch as the HTTP transports. Data comes
+//! either straight from the JSON data file or from a running server:
+//!
+//!     codegang-datasource-mcp --data-file ./codegang-datasource.json
+//!     codegang-datasource-mcp --server-url http://localhost:8080
+//!
+//! Write tools are only offered with `--allow-writes`. In server mode they
+//! are forwarded to the matching REST endpoints, authenticated with
+//! `$CODEGANG_API_KEY` if the server requires API keys.
+//!
+//! Notifications are written after the response to the message that caused
+//! them. Registry change notifications are only sent in file mode.
+
+use std::io::{BufRead, Write};
+
+use codegang_datasource::auth::Principal;
+use codegang_datasource::config::Config;
+use codegang_datasource::handlers::mcp::tools;
+use codegang_datasource::handlers::mcp::write_tools::forwarded_result;
+use codegang_datasource::handlers::mcp::{handle_message, notify_change, tool_result, Session, Transport};
+use codegang_datasource::model::Datasource;
+use codegang_datasource::storage::AppState;
+use tokio::sync::broadcast::error::TryRecvError;
+
+enum Source {
+    /// Open the data file directly. Do not point this at a file a running
+    /// server also writes to.
+    File(Box<AppState>),
+    /// Fetch a fresh snapshot from a server's REST API for every message
+    /// that reads registry data.
+    Server { base_url: String, config: Box<Config> },
+}
+
+/// A request to the server, with the API key from `$CODEGANG_API_KEY`.
+fn server_request(method: &str, url: &str) -> ureq::Request {
+    let request = ureq::request(method, url);
+    match std::env::var("CODEGANG_API_KEY") {
+        Ok(key) => request.set("Authorization", &format!("Bearer {key}")),
+        Err(_) => request,
+    }
+}
+
+/// Fetches the current datasource from a running server.
+fn fetch_snapshot(base_url: &str, config: &Config) -> Result<AppState, String> {
+    let ds: Datasource = server_request("GET", &format!("{base_url}/api/datasource"))
+        .call()
+        .map_err(|e| format!("GET {base_url}/api/datasource failed: {e}"))?
+        .into_json()
+        .map_err(|e| format!("Invalid datasource from {base_url}: {e}"))?;
+    Ok(AppState::in_memory(config, ds))
+}
+
+/// The state to handle `message` against: a fresh snapshot if it reads
+/// registry data, otherwise an empty registry, so that `initialize`, `ping`
+/// and notifications need no request to the server.
+fn snapshot(base_url: &str, config: &Config, message: &str) -> Result<AppState, String> {
+    let method = serde_json::from_str::<serde_json::Value>(message)
+        .ok()
+        .and_then(|msg| msg.get("method")?.as_str().map(str::to_string));
+    match method.as_deref() {
+        Some("tools/call" | "prompts/get" | "resources/list" | "resources/read" | "completion/complete") => {
+            fetch_snapshot(base_url, config)
+        }
+        _ => {
+            let empty = Datasource {
+                services: Vec::new(),
+                queue_contracts: Vec::new(),
+                nosql_contracts: Vec::new(),
+                proto_contracts: Vec::new(),
+            };
+            Ok(AppState::in_memory(config, empty))
+        }
+    }
+}
+
+/// Percent-encodes a single URL path segment.
+fn path_segment(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+/// Performs write tool `tool` through the server's REST API and returns its
+/// structured result.
+fn forward_write(base_url: &str, tool: &str, args: &serde_json::Value) -> Result<serde_json::Value, String> {
+    let arg = |name: &str| args.get(name).and_then(|v| v.as_str()).unwrap_or("");
+    let flags = || {
+        format!(
+            "?cascade={}&force={}",
+            args.get("cascade").and_then(|v| v.as_bool()).unwrap_or(false),
+            args.get("force").and_then(|v| v.as_bool()).unwrap_or(false)
+        )
+    };
+    let (method, path) = match tool {
+        "upsert_service" => ("POST", "/api/services".to_string()),
+        "delete_service" => ("DELETE", format!("/api/services/{}", path_segment(arg("name")))),
+        "upsert_queue_contract" => ("POST", "/api/queue-contracts".to_string()),
+        "delete_queue_contract" => ("DELETE", format!("/api/queue-contracts/{}{}", path_segment(arg("topic")), flags())),
+        "upsert_nosql_contract" => ("POST", "/api/nosql-contracts".to_string()),
+        "delete_nosql_contract" => ("DELETE", format!("/api/nosql-contracts/{}", path_segment(arg("entity")))),
+        "upsert_proto_contract" => ("POST", "/api/proto-contracts".to_string()),
+        "delete_proto_contract" => ("DELETE", format!("/api/proto-contracts/{}{}", path_segment(arg("name")), flags())),
+        _ => return Err(format!("Unknown write tool: {tool}")),
+    };
+
+    let request = server_request(method, &format!("{base_url}{path}"));
+    let result = if method == "POST" { request.send_json(args) } else { request.call() };
+    match result {
+        Ok(resp) => {
+            let body: serde_json::Value = resp.into_json().unwrap_or_default();
+            let updated = serde_json::from_value(body["updated_services"].clone()).unwrap_or_default();
+            let result = forwarded_result(tool, args, updated).ok_or_else(|| format!("Unknown write tool: {tool}"))?;
+            Ok(serde_json::to_value(result).unwrap())
+        }
+        Err(ureq::Error::Status(_, resp)) => {
+            let problem: serde_json::Value = resp.into_json().unwrap_or_default();
+            Err(problem.get("detail").and_then(|d| d.as_str()).unwrap_or("Request failed").to_string()
+                + &problem.get("errors").map(|e| format!(" {e}")).unwrap_or_default())
+        }
+        Err(e) => Err(format!("{method} {base_url}{path} failed: {e}")),
+    }
+}
+
+/// If `message` calls a write tool, forwards it and returns the response.
+fn forward_if_write(base_url: &str, message: &str) -> Option<String> {
+    let msg: serde_json::Value = serde_json::from_str(message).ok()?;
+    if msg.get("method")?.as_str()? != "tools/call" {
+        return None;
+    }
+    let params = msg.get("params")?;
+    let tool = tools::find(params.get("name")?.as_str()?).filter(|tool| tool.write)?;
+    let args = params.get("arguments").cloned().unwrap_or(serde_json::json!({}));
+    // Arguments are checked as in file mode, so no request is made with
+    // missing or mistyped fields.
+    let result = tool.check_args(&args).and_then(|()| forward_write(base_url, tool.name, &args));
+    let response = serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": msg.get("id").cloned().unwrap_or_default(),
+        "result": tool_result(result)
+    });
+    Some(response.to_string())
+}
+
+/// A JSON-RPC internal error for `message`, unless it is a notification.
+fn internal_error(message: &str, error: &str) -> Option<String> {
+    let id = serde_json::from_str::<serde_json::Value>(message).ok()?.get("id")?.clone();
+    let response = serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": -32603, "message": error }
+    });
+    Some(response.to_string())
+}
+
+fn usage() -> ! {
+    eprintln!("usage: codegang-datasource-mcp [--data-file PATH | --server-url URL] [--allow-writes]");
+    eprintln!("       (defaults: $CODEGANG_SERVER_URL, else $DATA_FILE)");
+    std::process::exit(2);
+}
+
+fn main() -> std::io::Result<()> {
+    let mut config = Config::data_only_from_env()?;
+    let mut server_url = std::env::var("CODEGANG_SERVER_URL").ok();
+    let mut allow_writes = false;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--data-file" => {
+                config.data_file = args.next().unwrap_or_else(|| usage());
+                server_url = None;
+            }
+            "--server-url" => server_url = Some(args.next().unwrap_or_else(|| usage())),
+            "--allow-writes" => allow_writes = true,
+            _ => usage(),
+        }
+    }
+
+    let source = match server_url {
+        Some(url) => Source::Server {
+            base_url: url.trim_end_matches('/').to_string(),
+            config: Box::new(config),
+        },
+        None => Source::File(Box::new(AppState::new(&config))),
+    };
+
+    // Whoever can run the binary can edit the data file; ownership is
+    // enforced by the server in --server-url mode.
+    let session = Session::new(Transport::Stdio, Principal::anonymous(), None, allow_writes);
+    let (session, mut changes) = match &source {
+        Source::File(state) => (session, Some(state.subscribe())),
+        Source::Server { .. } => (session.without_registry_notifications(), None),
+    };
+    let mut delivered = 0;
+    let stdin = std::io::stdin();
+    let mut stdout = std::io::stdout().lock();
+
+    for line in stdin.lock().lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match &source {
+            Source::File(state) => handle_message(&session, &line, state),
+            Source::Server { base_url, config } => {
+                let handle = |message: &str| {
+                    let forwarded = if allow_writes { forward_if_write(base_url, message) } else { None };
+                    forwarded.or_else(|| match snapshot(base_url, config, message) {
+                        Ok(snapshot) => handle_message(&session, message, &snapshot),
+                        Err(e) => {
+                            // stdout belongs to the protocol; diagnostics go to stderr.
+                            eprintln!("{e}");
+                            internal_error(message, &e)
+                        }
+                    })
+                };
+                // Batch members go one by one, so write tools are forwarded
+                // rather than applied to a snapshot.
+                match serde_json::from_str::<serde_json::Value>(&line) {
+                    Ok(serde_json::Value::Array(items)) if !items.is_empty() => {
+                        let responses: Vec<serde_json::Value> = items
+                            .iter()
+                            .filter_map(|item| handle(&item.to_string()))
+                            .filter_map(|response| serde_json::from_str(&response).ok())
+                            .collect();
+                        (!responses.is_empty()).then(|| serde_json::Value::Array(responses).to_string())
+                    }
+                    _ => handle(&line),
+                }
+            }
+        };
+        if let Some(response) = response {
+            writeln!(stdout, "{response}")?;
+        }
+
+        if let Some(changes) = &mut changes {
+            loop {
+                match changes.try_recv() {
+                    Ok(event) => notify_change(&session, Some(&event)),
+                    Err(TryRecvError::Lagged(_)) => notify_change(&session, None),
+                    Err(TryRecvError::Empty | TryRecvError::Closed) => break,
+                }
+            }
+        }
+        for (id, notification) in session.recorded_after(delivered) {
+            writeln!(stdout, "{notification}")?;
+            delivered = id;
+        }
+        stdout.flush()?;
+    }
+    Ok(())
+}
@@ -0,0 +1,219 @@
+use std::net::IpAddr;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::auth::jwt::JwtConfig;
+use crate::auth::{ApiKey, ApiKeys};
+use crate::validation::{CustomRule, DEFAULT_SERVICE_TYPES};
+
+/// Server configuration, read from environment variables at startup.
+pub struct Config {
+    /// Path to the JSON persistence file.
+    pub data_file: String,
+    /// Path of the JSON Lines audit log; `None` disables auditing.
+    pub audit_log_file: Option<String>,
+    /// Path the webhook subscriptions persist to, by default beside the data
+    /// file; `None` keeps them in memory only.
+    pub webhooks_file: Option<String>,
+    /// Attempts per webhook delivery before it is marked failed.
+    pub webhook_max_attempts: u32,
+    /// Wait before the first retry of a delivery; doubled for each further one.
+    pub webhook_retry_base: Duration,
+    /// What happens when a delete would leave dangling references.
+    pub referential_integrity: ReferentialIntegrity,
+    /// Values accepted for a service's `type`.
+    pub allowed_service_types: Vec<String>,
+    /// Extra service validation rules from `VALIDATION_RULES_FILE`.
+    pub validation_rules: Vec<CustomRule>,
+    /// Keys from `API_KEYS_FILE`. Without them, requests are not
+    /// authenticated.
+    pub api_keys: Option<ApiKeys>,
+    /// Bearer JWT validation, enabled by `JWT_JWKS_FILE` or `JWT_JWKS_URL`.
+    pub jwt: Option<JwtConfig>,
+    /// Bearer token that unlocks the MCP write tools for HTTP sessions when
+    /// API keys are not configured. Without it, write tools are disabled
+    /// for HTTP clients.
+    pub mcp_write_token: Option<String>,
+    /// MCP sessions without a live stream are closed after this long
+    /// without a client message.
+    pub mcp_session_idle_timeout: Duration,
+    /// Maximum number of concurrent MCP sessions.
+    pub mcp_max_sessions: usize,
+    /// Origin clients reach the server at, e.g. `https://registry.example.com`.
+    /// Without it, URLs are derived from `Forwarded`/`X-Forwarded-*` or
+    /// `Host` headers.
+    pub public_base_url: Option<String>,
+    /// Path under which a reverse proxy exposes the server, e.g. `/datasource`.
+    /// The proxy strips it; it only appears in URLs handed to clients.
+    pub path_prefix: String,
+    /// Announce the legacy MCP message endpoint as a relative URL.
+    pub mcp_relative_endpoint: bool,
+    /// Browser origins, besides localhost and `public_base_url`, allowed to
+    /// use the MCP endpoints; `*` allows any.
+    pub mcp_allowed_origins: Vec<String>,
+    /// Reverse proxies whose `Forwarded`/`X-Forwarded-For` headers are
+    /// believed when recording a client's address. Headers from any other
+    /// peer are ignored.
+    pub trusted_proxies: Vec<IpAddr>,
+}
+
+/// Server-wide default for deletes of contracts that services still
+/// reference. Callers can override it per request with `?cascade=true` or
+/// `?force=true`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReferentialIntegrity {
+    /// Reject the delete with 409 and list the referencing services.
+    Restrict,
+    /// Delete and strip the references from the services.
+    Cascade,
+    /// Delete without checking references.
+    Off,
+}
+
+/// The path of `file_name` in the directory of `path`.
+fn beside(path: &str, file_name: &str) -> String {
+    Path::new(path).with_file_name(file_name).display().to_string()
+}
+
+impl Config {
+    pub fn from_env() -> std::io::Result<Self> {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    /// Only the settings of the data file (`DATA_FILE`,
+    /// `REFERENTIAL_INTEGRITY`, `ALLOWED_SERVICE_TYPES`,
+    /// `VALIDATION_RULES_FILE`), for local tools such as the stdio MCP
+    /// server: no credentials are loaded, and nothing is audited or sent to
+    /// webhooks.
+    pub fn data_only_from_env() -> std::io::Result<Self> {
+        Self::from_vars(|name| match name {
+            "AUDIT_LOG_FILE" | "WEBHOOKS_FILE" => Some(String::new()),
+            "DATA_FILE" | "REFERENTIAL_INTEGRITY" | "ALLOWED_SERVICE_TYPES" | "VALIDATION_RULES_FILE" => {
+                std::env::var(name).ok()
+            }
+            _ => None,
+        })
+    }
+
+    /// Reads the configuration through `var`, which looks up a variable by
+    /// name, e.g. in a map instead of the environment.
+    pub fn from_vars(var: impl Fn(&str) -> Option<String>) -> std::io::Result<Self> {
+        let data_file = var("DATA_FILE").unwrap_or_else(|| "codegang-datasource.json".to_string());
+        let audit_log_file = match var("AUDIT_LOG_FILE") {
+            Some(path) => Some(path).filter(|p| !p.is_empty()),
+            None => Some(beside(&data_file, "codegang-audit.jsonl")),
+        };
+        let webhooks_file = match var("WEBHOOKS_FILE") {
+            Some(path) => Some(path).filter(|p| !p.is_empty()),
+            None => Some(beside(&data_file, "codegang-webhooks.json")),
+        };
+        let webhook_max_attempts = env_number(&var, "WEBHOOK_MAX_ATTEMPTS", 5)? as u32;
+        let webhook_retry_base = Duration::from_millis(env_number(&var, "WEBHOOK_RETRY_BASE_MS", 1000)?);
+        let referential_integrity = match var("REFERENTIAL_INTEGRITY").as_deref() {
+            None | Some("restrict") => ReferentialIntegrity::Restrict,
+            Some("cascade") => ReferentialIntegrity::Cascade,
+            Some("off") => ReferentialIntegrity::Off,
+            Some(other) => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("REFERENTIAL_INTEGRITY={other}: expected restrict, cascade or off"),
+                ))
+            }
+        };
+        let allowed_service_types = match var("ALLOWED_SERVICE_TYPES") {
+            Some(types) => types.split(',').map(|t| t.trim().to_string()).filter(|t| !t.is_empty()).collect(),
+            None => DEFAULT_SERVICE_TYPES.iter().map(|t| t.to_string()).collect(),
+        };
+        let validation_rules = match var("VALIDATION_RULES_FILE") {
+            Some(path) => {
+                let content = std::fs::read_to_string(&path)?;
+                serde_json::from_str(&content).map_err(|e| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidData, format!("{path}: {e}"))
+                })?
+            }
+            None => Vec::new(),
+        };
+        let api_keys = match var("API_KEYS_FILE") {
+            Some(path) => {
+                let content = std::fs::read_to_string(&path)?;
+                let keys: Vec<ApiKey> = serde_json::from_str(&content).map_err(|e| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidData, format!("{path}: {e}"))
+                })?;
+                Some(ApiKeys::new(keys))
+            }
+            None => None,
+        };
+        let jwt = JwtConfig::from_vars(&var)?;
+        let mcp_write_token = var("MCP_WRITE_TOKEN").filter(|t| !t.is_empty());
+        let mcp_session_idle_timeout = Duration::from_secs(env_number(&var, "MCP_SESSION_IDLE_TIMEOUT_SECS", 1800)?);
+        let mcp_max_sessions = env_number(&var, "MCP_MAX_SESSIONS", 1000)? as usize;
+        let public_base_url = var("PUBLIC_BASE_URL")
+            .map(|url| url.trim_end_matches('/').to_string())
+            .filter(|url| !url.is_empty());
+        let path_prefix = var("PATH_PREFIX")
+            .map(|prefix| match prefix.trim_matches('/') {
+                "" => String::new(),
+                trimmed => format!("/{trimmed}"),
+            })
+            .unwrap_or_default();
+        let mcp_relative_endpoint = matches!(var("MCP_RELATIVE_ENDPOINT").as_deref(), Some("true" | "1"));
+        let mcp_allowed_origins = var("MCP_ALLOWED_ORIGINS")
+            .map(|origins| {
+                origins
+                    .split(',')
+                    .map(|o| o.trim().trim_end_matches('/').to_lowercase())
+                    .filter(|o| !o.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let trusted_proxies = match var("TRUSTED_PROXIES") {
+            Some(proxies) => proxies
+                .split(',')
+                .map(str::trim)
+                .filter(|p| !p.is_empty())
+                .map(|p| {
+                    p.parse().map_err(|e| {
+                        std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("TRUSTED_PROXIES={p}: {e}"))
+                    })
+                })
+                .collect::<std::io::Result<_>>()?,
+            None => Vec::new(),
+        };
+        Ok(Self {
+            data_file,
+            audit_log_file,
+            webhooks_file,
+            webhook_max_attempts,
+            webhook_retry_base,
+            referential_integrity,
+            allowed_service_types,
+            validation_rules,
+            api_keys,
+            jwt,
+            mcp_write_token,
+            mcp_session_idle_timeout,
+            mcp_max_sessions,
+            public_base_url,
+            path_prefix,
+            mcp_relative_endpoint,
+            mcp_allowed_origins,
+            trusted_proxies,
+        })
+    }
+
+    /// Whether requests must carry an API key or JWT.
+    pub fn auth_enabled(&self) -> bool {
+        self.api_keys.is_some() || self.jwt.is_some()
+    }
+}
+
+/// Reads a non-negative integer variable, falling back to `default` when
+/// it is unset.
+pub(crate) fn env_number(var: impl Fn(&str) -> Option<String>, name: &str, default: u64) -> std::io::Result<u64> {
+    match var(name) {
+        Some(value) => value.trim().parse().map_err(|e| {
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("{name}={value}: {e}"))
+        }),
+        None => Ok(default),
+    }
+}
@@ -0,0 +1,309 @@
+use std::fmt;
+
+use actix_web::error::{JsonPayloadError, PathError, QueryPayloadError};
+use actix_web::http::{header, StatusCode};
+use actix_web::{HttpRequest, HttpResponse, ResponseError};
+use serde::{Deserialize, Serialize};
+
+use crate::storage::StorageError;
+
+/// Stable, machine-readable error codes. The names from `as_str` are part
+/// of the API and must not change; they are both the problem `code` and the
+/// last segment of its `type`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    NotFound,
+    RouteNotFound,
+    InvalidBody,
+    InvalidQuery,
+    InvalidPath,
+    InvalidPatch,
+    UnsupportedMediaType,
+    ValidationFailed,
+    ReferenceConflict,
+    BatchRejected,
+    SessionNotFound,
+    MissingSessionId,
+    UnsupportedProtocolVersion,
+    TooManySessions,
+    SessionOverloaded,
+    Unauthorized,
+    Forbidden,
+    Internal,
+    NotReady,
+}
+
+impl ErrorCode {
+    pub const ALL: [ErrorCode; 19] = [
+        ErrorCode::NotFound,
+        ErrorCode::RouteNotFound,
+        ErrorCode::InvalidBody,
+        ErrorCode::InvalidQuery,
+        ErrorCode::InvalidPath,
+        ErrorCode::InvalidPatch,
+        ErrorCode::UnsupportedMediaType,
+        ErrorCode::ValidationFailed,
+        ErrorCode::ReferenceConflict,
+        ErrorCode::BatchRejected,
+        ErrorCode::SessionNotFound,
+        ErrorCode::MissingSessionId,
+        ErrorCode::UnsupportedProtocolVersion,
+        ErrorCode::TooManySessions,
+        ErrorCode::SessionOverloaded,
+        ErrorCode::Unauthorized,
+        ErrorCode::Forbidden,
+        ErrorCode::Internal,
+        ErrorCode::NotReady,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::NotFound => "not_found",
+            ErrorCode::RouteNotFound => "route_not_found",
+            ErrorCode::InvalidBody => "invalid_body",
+            ErrorCode::InvalidQuery => "invalid_query",
+            ErrorCode::InvalidPath => "invalid_path",
+            ErrorCode::InvalidPatch => "invalid_patch",
+            ErrorCode::UnsupportedMediaType => "unsupported_media_type",
+            ErrorCode::ValidationFailed => "validation_failed",
+            ErrorCode::ReferenceConflict => "reference_conflict",
+            ErrorCode::BatchRejected => "batch_rejected",
+            ErrorCode::SessionNotFound => "session_not_found",
+            ErrorCode::MissingSessionId => "missing_session_id",
+            ErrorCode::UnsupportedProtocolVersion => "unsupported_protocol_version",
+            ErrorCode::TooManySessions => "too_many_sessions",
+            ErrorCode::SessionOverloaded => "session_overloaded",
+            ErrorCode::Unauthorized => "unauthorized",
+            ErrorCode::Forbidden => "forbidden",
+            ErrorCode::Internal => "internal_error",
+            ErrorCode::NotReady => "not_ready",
+        }
+    }
+
+    pub fn status(self) -> StatusCode {
+        match self {
+            ErrorCode::NotFound | ErrorCode::RouteNotFound | ErrorCode::SessionNotFound => StatusCode::NOT_FOUND,
+            ErrorCode::InvalidBody
+            | ErrorCode::InvalidQuery
+            | ErrorCode::InvalidPath
+            | ErrorCode::MissingSessionId
+            | ErrorCode::UnsupportedProtocolVersion => StatusCode::BAD_REQUEST,
+            ErrorCode::InvalidPatch | ErrorCode::ValidationFailed | ErrorCode::BatchRejected => {
+                StatusCode::UNPROCESSABLE_ENTITY
+            }
+            ErrorCode::UnsupportedMediaType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            ErrorCode::ReferenceConflict => StatusCode::CONFLICT,
+            ErrorCode::TooManySessions | ErrorCode::SessionOverloaded | ErrorCode::NotReady => {
+                StatusCode::SERVICE_UNAVAILABLE
+            }
+            ErrorCode::Unauthorized => StatusCode::UNAUTHORIZED,
+            ErrorCode::Forbidden => StatusCode::FORBIDDEN,
+            ErrorCode::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// Short, human-readable summary used as the problem `title`.
+    pub fn title(self) -> &'static str {
+        match self {
+            ErrorCode::NotFound => "Entity not found",
+            ErrorCode::RouteNotFound => "Route not found",
+            ErrorCode::InvalidBody => "Invalid request body",
+            ErrorCode::InvalidQuery => "Invalid query string",
+            ErrorCode::InvalidPath => "Invalid path parameter",
+            ErrorCode::InvalidPatch => "Invalid patch",
+            ErrorCode::UnsupportedMediaType => "Unsupported media type",
+            ErrorCode::ValidationFailed => "Validation failed",
+            ErrorCode::ReferenceConflict => "Entity is still referenced",
+            ErrorCode::BatchRejected => "Batch rejected",
+            ErrorCode::SessionNotFound => "Session not found",
+            ErrorCode::MissingSessionId => "Missing session id",
+            ErrorCode::UnsupportedProtocolVersion => "Unsupported protocol version",
+            ErrorCode::TooManySessions => "Too many sessions",
+            ErrorCode::SessionOverloaded => "Session is not reading its stream",
+            ErrorCode::Unauthorized => "Authentication required",
+            ErrorCode::Forbidden => "Insufficient scope",
+            ErrorCode::Internal => "Internal server error",
+            ErrorCode::NotReady => "Not ready",
+        }
+    }
+}
+
+impl Serialize for ErrorCode {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+/// A problem with one field of the request, addressed by JSON Pointer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldError {
+    pub path: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(path: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            message: message.into(),
+        }
+    }
+
+    /// The same problem addressed from `prefix`, e.g. the operation of a
+    /// batch the field belongs to.
+    pub fn under(self, prefix: &str) -> Self {
+        Self {
+            path: format!("{prefix}{}", self.path),
+            ..self
+        }
+    }
+
+    /// One line about `errors`, for a problem's `detail`.
+    pub fn summary(errors: &[FieldError]) -> String {
+        match errors {
+            [error] => format!("Field '{}' is invalid", error.path),
+            errors => format!("{} fields are invalid", errors.len()),
+        }
+    }
+}
+
+/// Renders a deserialization path as a JSON Pointer, e.g. `/queue/publish_queues/0`.
+pub fn json_pointer(path: &serde_path_to_error::Path) -> String {
+    use serde_path_to_error::Segment;
+    path.iter()
+        .filter_map(|segment| match segment {
+            Segment::Seq { index } => Some(format!("/{index}")),
+            Segment::Map { key } => Some(format!("/{}", key.replace('~', "~0").replace('/', "~1"))),
+            Segment::Enum { .. } | Segment::Unknown => None,
+        })
+        .collect()
+}
+
+/// The single error type returned by every REST handler, rendered as an
+/// RFC 7807 `application/problem+json` document.
+#[derive(Debug)]
+pub struct ApiError {
+    pub code: ErrorCode,
+    pub detail: String,
+    pub errors: Vec<FieldError>,
+    /// Extra problem members, e.g. `referenced_by` or batch `results`.
+    pub extensions: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Serialize)]
+struct ProblemDocument<'a> {
+    #[serde(rename = "type")]
+    problem_type: String,
+    title: &'static str,
+    status: u16,
+    detail: &'a str,
+    code: ErrorCode,
+    #[serde(skip_serializing_if = "<[FieldError]>::is_empty")]
+    errors: &'a [FieldError],
+    #[serde(flatten)]
+    extensions: &'a serde_json::Map<String, serde_json::Value>,
+}
+
+impl ApiError {
+    pub fn new(code: ErrorCode, detail: impl Into<String>) -> Self {
+        Self {
+            code,
+            detail: detail.into(),
+            errors: Vec::new(),
+            extensions: serde_json::Map::new(),
+        }
+    }
+
+    pub fn not_found(detail: impl Into<String>) -> Self {
+        Self::new(ErrorCode::NotFound, detail)
+    }
+
+    pub fn with_errors(mut self, errors: Vec<FieldError>) -> Self {
+        self.errors = errors;
+        self
+    }
+
+    pub fn with_extension(mut self, key: &str, value: impl Serialize) -> Self {
+        self.extensions.insert(
+            key.to_string(),
+            serde_json::to_value(value).unwrap_or(serde_json::Value::Null),
+        );
+        self
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.code.as_str(), self.detail)
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        self.code.status()
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let status = self.status_code();
+        let doc = ProblemDocument {
+            problem_type: format!("urn:codegang:error:{}", self.code.as_str()),
+            title: self.code.title(),
+            status: status.as_u16(),
+            detail: &self.detail,
+            code: self.code,
+            errors: &self.errors,
+            extensions: &self.extensions,
+        };
+        HttpResponse::build(status)
+            .insert_header((header::CONTENT_TYPE, "application/problem+json"))
+            .body(serde_json::to_string(&doc).unwrap_or_default())
+    }
+}
+
+impl From<StorageError> for ApiError {
+    fn from(err: StorageError) -> Self {
+        match err {
+            StorageError::NotFound(m) => ApiError::not_found(m),
+            StorageError::Forbidden(m) => ApiError::new(ErrorCode::Forbidden, m),
+            StorageError::Invalid(m) => ApiError::new(ErrorCode::InvalidPatch, m),
+            StorageError::Unavailable(m) => ApiError::new(ErrorCode::NotReady, m),
+            StorageError::Validation(errors) => {
+                ApiError::new(ErrorCode::ValidationFailed, FieldError::summary(&errors)).with_errors(errors)
+            }
+            StorageError::Referenced { message, services } => {
+                ApiError::new(ErrorCode::ReferenceConflict, message).with_extension("referenced_by", services)
+            }
+        }
+    }
+}
+
+// ── Extractor error handlers ─────────────────────────────────────
+
+/// Converts `web::Json` extraction failures into problem documents.
+pub fn json_error_handler(err: JsonPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    let api_err = match &err {
+        JsonPayloadError::ContentType => ApiError::new(
+            ErrorCode::UnsupportedMediaType,
+            "Expected a request body with Content-Type: application/json",
+        ),
+        JsonPayloadError::Deserialize(e) => ApiError::new(ErrorCode::InvalidBody, e.to_string()),
+        _ => ApiError::new(ErrorCode::InvalidBody, err.to_string()),
+    };
+    api_err.into()
+}
+
+pub fn query_error_handler(err: QueryPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    ApiError::new(ErrorCode::InvalidQuery, err.to_string()).into()
+}
+
+pub fn path_error_handler(err: PathError, _req: &HttpRequest) -> actix_web::Error {
+    ApiError::new(ErrorCode::InvalidPath, err.to_string()).into()
+}
+
+/// Fallback for requests that match no route.
+pub async fn route_not_found(req: HttpRequest) -> Result<HttpResponse, ApiError> {
+    Err(ApiError::new(
+        ErrorCode::RouteNotFound,
+        format!("No route for {} {}", req.method(), req.path()),
+    ))
+}
@@ -0,0 +1,18 @@
+use actix_web::{web, HttpResponse};
+
+use crate::auth::Principal;
+use crate::error::ApiError;
+use crate::handlers::mcp::{SessionInfo, Sessions};
+
+/// GET /api/admin/mcp-sessions
+pub async fn mcp_sessions(sessions: web::Data<Sessions>, principal: Principal) -> Result<HttpResponse, ApiError> {
+    principal.require_admin()?;
+    let mut list: Vec<SessionInfo> = sessions
+        .read()
+        .await
+        .iter()
+        .map(|(id, session)| SessionInfo::new(id, session))
+        .collect();
+    list.sort_by_key(|s| std::cmp::Reverse(s.age_secs));
+    Ok(HttpResponse::Ok().json(list))
+}
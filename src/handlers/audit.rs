@@ -0,0 +1,85 @@
+use std::time::SystemTime;
+
+use actix_web::{web, HttpResponse};
+use serde::Deserialize;
+
+use crate::auth::Principal;
+use crate::error::{ApiError, ErrorCode};
+use crate::model::EntityKind;
+use crate::storage::AppState;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditFormat {
+    /// A JSON array.
+    #[default]
+    Json,
+    /// JSON Lines, one entry per line, for export.
+    Jsonl,
+}
+
+#[derive(Deserialize)]
+pub struct AuditQuery {
+    /// Only entries at or after this RFC 3339 time.
+    since: Option<String>,
+    /// Only entries before this RFC 3339 time.
+    until: Option<String>,
+    /// Only changes by this principal.
+    actor: Option<String>,
+    kind: Option<EntityKind>,
+    key: Option<String>,
+    /// Only the most recent entries that match.
+    limit: Option<usize>,
+    #[serde(default)]
+    format: AuditFormat,
+}
+
+fn parse_time(name: &str, value: &Option<String>) -> Result<Option<SystemTime>, ApiError> {
+    value
+        .as_deref()
+        .map(|v| {
+            humantime::parse_rfc3339_weak(v)
+                .map_err(|e| ApiError::new(ErrorCode::InvalidQuery, format!("Invalid '{name}' time '{v}': {e}")))
+        })
+        .transpose()
+}
+
+/// GET /api/audit?since=&until=&actor=&kind=&key=&limit=&format=json|jsonl
+pub async fn list(
+    state: web::Data<AppState>,
+    principal: Principal,
+    query: web::Query<AuditQuery>,
+) -> Result<HttpResponse, ApiError> {
+    principal.require_admin()?;
+    let Some(log) = state.audit_log() else {
+        return Err(ApiError::new(ErrorCode::RouteNotFound, "The audit log is disabled"));
+    };
+    let since = parse_time("since", &query.since)?;
+    let until = parse_time("until", &query.until)?;
+    let mut entries = log
+        .entries()
+        .map_err(|e| ApiError::new(ErrorCode::Internal, format!("Failed to read the audit log: {e}")))?;
+    entries.retain(|entry| {
+        let time = entry.time();
+        since.is_none_or(|since| time.is_some_and(|t| t >= since))
+            && until.is_none_or(|until| time.is_some_and(|t| t < until))
+            && query.actor.as_ref().is_none_or(|actor| entry.actor.name == *actor)
+            && query.kind.is_none_or(|kind| entry.kind == kind)
+            && query.key.as_ref().is_none_or(|key| entry.key == *key)
+    });
+    if let Some(limit) = query.limit {
+        entries.drain(..entries.len().saturating_sub(limit));
+    }
+
+    match query.format {
+        AuditFormat::Json => Ok(HttpResponse::Ok().json(entries)),
+        AuditFormat::Jsonl => {
+            let mut body = String::new();
+            for entry in &entries {
+                body.push_str(&serde_json::to_string(entry).unwrap_or_default());
+                body.push('\n');
+            }
+            Ok(HttpResponse::Ok().content_type("application/jsonl").body(body))
+        }
+    }
+}
@@ -0,0 +1,27 @@
+use actix_web::{web, HttpResponse};
+
+use crate::audit::Actor;
+use crate::error::{ApiError, ErrorCode};
+use crate::model::BatchRequest;
+use crate::storage::{AppState, BatchError};
+
+/// POST /api/batch — apply upserts/deletes across all entity kinds atomically
+pub async fn apply(
+    state: web::Data<AppState>,
+    actor: Actor,
+    body: super::Json<BatchRequest>,
+) -> Result<HttpResponse, ApiError> {
+    match state.apply_batch(&body.operations, &actor) {
+        Ok(results) => Ok(HttpResponse::Ok().json(serde_json::json!({"status": "ok", "results": results}))),
+        Err(BatchError::Forbidden { index, message }) => {
+            Err(ApiError::new(ErrorCode::Forbidden, format!("Operation {index}: {message}")))
+        }
+        Err(BatchError::Rejected(results)) => {
+            let errors = results.iter().flat_map(|r| r.errors.clone()).collect();
+            Err(ApiError::new(ErrorCode::BatchRejected, "Batch rejected; no operations were applied")
+                .with_errors(errors)
+                .with_extension("results", results))
+        }
+        Err(BatchError::Unavailable(message)) => Err(ApiError::new(ErrorCode::NotReady, message)),
+    }
+}
@@ -1,5 +1,8 @@
 use actix_web::{web, HttpResponse};
 
+use crate::audit::Actor;
+use crate::auth::Principal;
+use crate::error::ApiError;
 use crate::model::Datasource;
 use crate::storage::AppState;
 
@@ -9,7 +12,13 @@ pub async fn get(state: web::Data<AppState>) -> HttpResponse {
 }
 
 /// PUT /api/datasource
-pub async fn replace(state: web::Data<AppState>, body: web::Json<Datasource>) -> HttpResponse {
-    state.replace_datasource(body.into_inner());
-    HttpResponse::Ok().json(state.get_datasource())
+pub async fn replace(
+    state: web::Data<AppState>,
+    principal: Principal,
+    actor: Actor,
+    body: super::Json<Datasource>,
+) -> Result<HttpResponse, ApiError> {
+    principal.require_admin()?;
+    state.replace_datasource(body.into_inner(), &actor)?;
+    Ok(HttpResponse::Ok().json(state.get_datasource()))
 }
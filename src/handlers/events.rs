@@ -0,0 +1,128 @@
+//! `GET /api/events`: every change to the registry as Server-Sent Events,
+//! named after the action (`created`, `updated`, `deleted`). Event ids carry
+//! the revision, so a reconnecting client that sends `Last-Event-ID` gets
+//! what it missed from the change log. When that is not possible (the
+//! changes are no longer kept, or the server restarted), the stream starts
+//! with a `reset` event and the client should reload what it shows.
+
+use std::sync::LazyLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use actix_web::http::header;
+use actix_web::{web, HttpRequest, HttpResponse};
+use actix_web_lab::sse;
+use serde_json::json;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::watch;
+
+use crate::model::ChangeEvent;
+use crate::storage::AppState;
+
+/// Distinguishes this server run in event ids, since revisions restart
+/// from 1 with the process.
+static EPOCH: LazyLock<u128> =
+    LazyLock::new(|| SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis());
+
+/// Set on shutdown to end every open stream.
+static CLOSING: LazyLock<watch::Sender<bool>> = LazyLock::new(|| watch::channel(false).0);
+
+/// Ends open event streams so the server can stop without waiting on them.
+pub fn drain() {
+    CLOSING.send_replace(true);
+}
+
+fn event_id(revision: u64) -> String {
+    format!("{}-{revision}", *EPOCH)
+}
+
+/// The revision in an id of ours from this run.
+fn parse_event_id(id: &str) -> Option<u64> {
+    let (epoch, revision) = id.split_once('-')?;
+    if epoch.parse::<u128>().ok()? != *EPOCH {
+        return None;
+    }
+    revision.parse().ok()
+}
+
+fn change(event: &ChangeEvent) -> sse::Event {
+    let data = json!({
+        "revision": event.revision,
+        "kind": event.kind,
+        "key": event.key,
+        "action": event.action,
+        "changed_fields": event.changed_fields(),
+    });
+    let name = serde_json::to_value(event.action)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default();
+    sse::Event::Data(sse::Data::new(data.to_string()).event(name).id(event_id(event.revision)))
+}
+
+fn reset(revision: u64) -> sse::Event {
+    let data = json!({ "revision": revision });
+    sse::Event::Data(sse::Data::new(data.to_string()).event("reset").id(event_id(revision)))
+}
+
+pub async fn stream(req: HttpRequest, state: web::Data<AppState>) -> HttpResponse {
+    // Subscribe before reading the change log, so nothing committed in
+    // between is lost; live events already replayed are skipped below.
+    let mut changes = state.subscribe();
+    let mut closing = CLOSING.subscribe();
+    let last_event_id = req.headers().get("Last-Event-ID").and_then(|h| h.to_str().ok());
+
+    let mut first = Vec::new();
+    let mut sent = 0;
+    if let Some(id) = last_event_id {
+        match parse_event_id(id).and_then(|revision| Some((revision, state.changes_since(revision)?))) {
+            Some((revision, missed)) => {
+                sent = missed.last().map_or(revision, |e| e.revision);
+                first.extend(missed.iter().map(change));
+            }
+            None => {
+                sent = state.revision();
+                first.push(reset(sent));
+            }
+        }
+    }
+
+    let stream = async_stream::stream! {
+        for event in first {
+            yield Ok::<_, std::convert::Infallible>(event);
+        }
+        loop {
+            let next = tokio::select! {
+                next = tokio::time::timeout(Duration::from_secs(30), changes.recv()) => next,
+                _ = closing.wait_for(|closing| *closing) => break,
+            };
+            match next {
+                Ok(Ok(event)) => {
+                    if event.revision > sent {
+                        sent = event.revision;
+                        yield Ok(change(&event));
+                    }
+                }
+                // Fell behind the broadcast: catch up from the change log.
+                Ok(Err(RecvError::Lagged(_))) => match state.changes_since(sent) {
+                    Some(missed) => {
+                        for event in missed {
+                            sent = event.revision;
+                            yield Ok(change(&event));
+                        }
+                    }
+                    None => {
+                        sent = state.revision();
+                        yield Ok(reset(sent));
+                    }
+                },
+                Ok(Err(RecvError::Closed)) => break,
+                Err(_) => yield Ok(sse::Event::Comment("keepalive".into())),
+            }
+        }
+    };
+
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header((header::CACHE_CONTROL, "no-cache"))
+        .body(sse::Sse::from_stream(stream).with_keep_alive(Duration::from_secs(15)))
+}
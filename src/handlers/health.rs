@@ -0,0 +1,57 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use actix_web::{web, HttpResponse};
+
+use crate::error::{ApiError, ErrorCode};
+use crate::handlers::mcp::{Sessions, Transport};
+use crate::metrics;
+use crate::storage::AppState;
+
+/// GET /healthz — the process is up and serving requests.
+pub async fn healthz() -> HttpResponse {
+    HttpResponse::Ok().json(serde_json::json!({"status": "ok"}))
+}
+
+/// GET /readyz — the data file was loaded and can be written.
+pub async fn readyz(state: web::Data<AppState>) -> Result<HttpResponse, ApiError> {
+    state
+        .check_ready()
+        .map_err(|detail| ApiError::new(ErrorCode::NotReady, detail))?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"status": "ready"})))
+}
+
+/// GET /metrics — Prometheus text exposition format.
+pub async fn metrics(state: web::Data<AppState>, sessions: web::Data<Sessions>) -> HttpResponse {
+    let mut out = String::new();
+    state.metrics().write(&mut out);
+
+    metrics::header(&mut out, "codegang_entities", "gauge", "Entities in the registry by kind.");
+    for (kind, count) in state.entity_counts() {
+        let _ = writeln!(out, "codegang_entities{{kind=\"{}\"}} {count}", label(kind));
+    }
+
+    let mut open: BTreeMap<String, usize> = [Transport::Sse, Transport::StreamableHttp]
+        .into_iter()
+        .map(|transport| (label(transport), 0))
+        .collect();
+    for session in sessions.read().await.values() {
+        *open.entry(label(session.transport)).or_default() += 1;
+    }
+    metrics::header(&mut out, "codegang_mcp_sessions", "gauge", "Open MCP sessions by transport.");
+    for (transport, count) in open {
+        let _ = writeln!(out, "codegang_mcp_sessions{{transport=\"{transport}\"}} {count}");
+    }
+
+    HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4; charset=utf-8")
+        .body(out)
+}
+
+/// The serialized name of a unit enum variant.
+fn label(value: impl serde::Serialize) -> String {
+    serde_json::to_value(value)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
@@ -0,0 +1,61 @@
+//! `completion/complete`: suggests entity keys for prompt arguments,
+//! resource template variables and tool arguments.
+
+use crate::model::EntityKind;
+use crate::storage::AppState;
+
+/// Most values returned in one completion.
+const MAX_VALUES: usize = 100;
+
+/// The kind of entity a completable argument names, if any.
+fn argument_kind(reference: &serde_json::Value, argument: &str) -> Option<EntityKind> {
+    let field = |name: &str| reference.get(name).and_then(|v| v.as_str()).unwrap_or("");
+    match (field("type"), argument) {
+        ("ref/prompt", "service") => Some(EntityKind::Service),
+        ("ref/prompt", "topic") => Some(EntityKind::QueueContract),
+        ("ref/prompt", "contract") => Some(EntityKind::ProtoContract),
+        ("ref/resource", _) => super::resources::parse_uri(field("uri")).map(|(kind, _)| kind),
+        // Not part of the MCP spec; lets clients complete tool arguments too.
+        ("ref/tool", "topic") => Some(EntityKind::QueueContract),
+        ("ref/tool", "entity") => Some(EntityKind::NosqlContract),
+        ("ref/tool", "name") if field("name").contains("proto") => Some(EntityKind::ProtoContract),
+        ("ref/tool", "name") if field("name").ends_with("_service") => Some(EntityKind::Service),
+        _ => None,
+    }
+}
+
+fn keys(state: &AppState, kind: EntityKind) -> Vec<String> {
+    match kind {
+        EntityKind::Service => state.get_services().into_iter().map(|s| s.name).collect(),
+        EntityKind::QueueContract => state.get_queue_contracts().into_iter().map(|q| q.topic_name).collect(),
+        EntityKind::NosqlContract => state.get_nosql_contracts().into_iter().map(|n| n.entity_name).collect(),
+        EntityKind::ProtoContract => state.get_proto_contracts().into_iter().map(|p| p.name).collect(),
+    }
+}
+
+/// Result of `completion/complete`. Keys starting with the typed value come
+/// first, then keys containing it anywhere, case-insensitively. `Err`
+/// carries the message for an invalid-params error.
+pub fn complete(params: &serde_json::Value, state: &AppState) -> Result<serde_json::Value, String> {
+    let reference = params.get("ref").ok_or("Missing 'ref'")?;
+    let argument = params.get("argument").ok_or("Missing 'argument'")?;
+    let name = argument.get("name").and_then(|v| v.as_str()).ok_or("Missing 'argument.name'")?;
+    let value = argument.get("value").and_then(|v| v.as_str()).unwrap_or("").to_lowercase();
+
+    let mut values = match argument_kind(reference, name) {
+        Some(kind) => keys(state, kind),
+        None => Vec::new(),
+    };
+    values.retain(|k| k.to_lowercase().contains(&value));
+    values.sort_by_key(|k| (!k.to_lowercase().starts_with(&value), k.clone()));
+
+    let total = values.len();
+    values.truncate(MAX_VALUES);
+    Ok(serde_json::json!({
+        "completion": {
+            "values": values,
+            "total": total,
+            "hasMore": total > MAX_VALUES
+        }
+    }))
+}
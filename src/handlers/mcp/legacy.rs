@@ -0,0 +1,127 @@
+//! The 2024-11-05 HTTP+SSE transport: the client opens `GET /sse`, learns
+//! its message URL from the `endpoint` event and POSTs JSON-RPC requests
+//! there; responses arrive on the SSE stream.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use actix_web_lab::sse;
+use serde::Deserialize;
+use tokio::sync::mpsc;
+
+use super::{
+    check_origin, has_write_access, process, register, SendError, Session, Sessions, Transport, STREAM_BUFFER,
+};
+use crate::audit::source_ip;
+use crate::auth::Principal;
+use crate::config::Config;
+use crate::error::{ApiError, ErrorCode};
+use crate::handlers::{public_url, Json};
+use crate::storage::AppState;
+
+// ── GET /sse ─────────────────────────────────────────────────────
+
+pub async fn sse_handler(
+    req: HttpRequest,
+    principal: Principal,
+    config: web::Data<Config>,
+    sessions: web::Data<Sessions>,
+) -> Result<impl Responder, ApiError> {
+    check_origin(&req, &config)?;
+    let (tx, mut rx) = mpsc::channel::<sse::Event>(STREAM_BUFFER);
+
+    let write_access = has_write_access(&req, &principal, &config);
+    let session = Session::new(Transport::Sse, principal, source_ip(&req), write_access);
+    session.attach(tx);
+    let session_id = register(&sessions, Arc::new(session), &config).await?;
+
+    let path = format!("/message?session_id={session_id}");
+    let endpoint_url = if config.mcp_relative_endpoint {
+        format!("{}{path}", config.path_prefix)
+    } else {
+        public_url(&req, &config, &path)
+    };
+
+    let session_id_clone = session_id.clone();
+    let sessions_clone = sessions.into_inner().clone();
+
+    let stream = async_stream::stream! {
+        // First event: tell client where to POST messages
+        yield Ok::<_, std::convert::Infallible>(sse::Event::Data(
+            sse::Data::new(endpoint_url)
+                .event("endpoint")
+        ));
+
+        // Stream responses
+        loop {
+            match tokio::time::timeout(Duration::from_secs(30), rx.recv()).await {
+                Ok(Some(event)) => yield Ok::<_, std::convert::Infallible>(event),
+                Ok(None) => break, // channel closed
+                Err(_) => {
+                    // Send keepalive comment
+                    yield Ok::<_, std::convert::Infallible>(sse::Event::Comment("keepalive".into()));
+                }
+            }
+        }
+
+        // Cleanup session
+        sessions_clone.write().await.remove(&session_id_clone);
+    };
+
+    Ok(sse::Sse::from_stream(stream)
+        .with_keep_alive(Duration::from_secs(15)))
+}
+
+// ── POST /message ────────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct MessageQuery {
+    session_id: String,
+}
+
+pub async fn message_handler(
+    req: HttpRequest,
+    principal: Principal,
+    query: web::Query<MessageQuery>,
+    body: Json<serde_json::Value>,
+    state: web::Data<AppState>,
+    config: web::Data<Config>,
+    sessions: web::Data<Sessions>,
+) -> Result<HttpResponse, ApiError> {
+    check_origin(&req, &config)?;
+    let session_id = &query.session_id;
+
+    let sessions_read = sessions.read().await;
+    let session = match sessions_read.get(session_id) {
+        Some(session) => session.clone(),
+        None => {
+            return Err(ApiError::new(
+                ErrorCode::SessionNotFound,
+                format!("Session '{session_id}' not found"),
+            ));
+        }
+    };
+    drop(sessions_read);
+    session.check_caller(&principal)?;
+    session.set_caller(principal, source_ip(&req));
+
+    // Notifications have no id and expect no response
+    if let Some(response) = process(&session, body.into_inner(), &state) {
+        return deliver(&session, response);
+    }
+
+    Ok(HttpResponse::Accepted().finish())
+}
+
+/// Sends a response on the session's stream. A client that stops reading
+/// gets 503 instead of blocking the request.
+fn deliver(session: &Session, json: String) -> Result<HttpResponse, ApiError> {
+    match session.send(json) {
+        Err(SendError::Full) => Err(ApiError::new(
+            ErrorCode::SessionOverloaded,
+            "The session's event stream is full; read pending events before sending more requests",
+        )),
+        Ok(()) | Err(SendError::NoStream) => Ok(HttpResponse::Accepted().finish()),
+    }
+}
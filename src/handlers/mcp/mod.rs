@@ -0,0 +1,812 @@
+//! Model Context Protocol server. The transports share the JSON-RPC
+//! dispatch in this module: the legacy 2024-11-05 HTTP+SSE transport
+//! (`legacy`), the Streamable HTTP transport (`streamable`) and stdio via
+//! the `codegang-datasource-mcp` binary (`handle_message`).
+
+pub mod completion;
+pub mod legacy;
+pub mod prompts;
+pub mod resources;
+pub mod streamable;
+pub mod tools;
+pub mod views;
+pub mod write_tools;
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use actix_web::http::header;
+use actix_web::HttpRequest;
+use actix_web_lab::sse;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::{mpsc, RwLock};
+
+use self::tools::ToolContext;
+use crate::audit::Actor;
+use crate::auth::Principal;
+use crate::config::Config;
+use crate::error::{ApiError, ErrorCode};
+use crate::model::{ChangeAction, ChangeEvent};
+use crate::storage::AppState;
+
+/// Protocol revisions this server speaks, newest first.
+pub const PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
+
+/// Number of server→client messages kept per session for resumption.
+const HISTORY_LIMIT: usize = 100;
+
+/// Messages buffered per SSE stream before a slow client's messages are
+/// dropped.
+pub const STREAM_BUFFER: usize = 32;
+
+// ── Session registry ─────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Transport {
+    /// `GET /sse` + `POST /message?session_id=`.
+    Sse,
+    /// Single `/mcp` endpoint with an `Mcp-Session-Id` header.
+    StreamableHttp,
+    /// Newline-delimited JSON-RPC over stdin/stdout.
+    Stdio,
+}
+
+pub struct Session {
+    /// The `Mcp-Session-Id` or legacy `session_id`.
+    pub id: String,
+    pub transport: Transport,
+    /// The caller whose permissions write tools are checked against. Only
+    /// the caller that opened the session may use it.
+    principal: Mutex<Principal>,
+    /// Address of the caller's latest request, for the audit log.
+    source_ip: Mutex<Option<String>>,
+    /// Decides which tools are listed and callable in this session.
+    tool_context: Mutex<ToolContext>,
+    protocol_version: Mutex<Option<String>>,
+    /// The currently attached server→client SSE stream, if any.
+    stream: Mutex<Option<mpsc::Sender<sse::Event>>>,
+    next_event_id: AtomicU64,
+    /// Recent messages with their SSE event ids, for `Last-Event-ID` replay.
+    history: Mutex<VecDeque<(u64, String)>>,
+    /// Resource URIs the client asked to be notified about.
+    subscriptions: Mutex<HashSet<String>>,
+    /// `clientInfo` from `initialize`.
+    client_info: Mutex<Option<serde_json::Value>>,
+    created_at: SystemTime,
+    last_activity: Mutex<SystemTime>,
+    /// Messages not delivered live because the stream buffer was full.
+    dropped: AtomicU64,
+    /// Minimum level of `notifications/message` sent, set by
+    /// `logging/setLevel`. No log messages are sent until then.
+    log_level: Mutex<Option<LogLevel>>,
+    /// Whether registry changes reach this session, and so whether
+    /// `initialize` offers resource subscriptions and `list_changed`.
+    registry_notifications: bool,
+}
+
+/// Syslog severities used by `logging/setLevel` and `notifications/message`,
+/// least severe first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Debug,
+    Info,
+    Notice,
+    Warning,
+    Error,
+    Critical,
+    Alert,
+    Emergency,
+}
+
+/// Why `Session::send` could not deliver a message live.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SendError {
+    /// No stream is attached, or the client went away.
+    NoStream,
+    /// The client is not reading its stream fast enough.
+    Full,
+}
+
+impl Session {
+    pub fn new(transport: Transport, principal: Principal, source_ip: Option<String>, write_access: bool) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            transport,
+            principal: Mutex::new(principal),
+            source_ip: Mutex::new(source_ip),
+            tool_context: Mutex::new(ToolContext {
+                write_access,
+                workspace: None,
+            }),
+            protocol_version: Mutex::new(None),
+            stream: Mutex::new(None),
+            next_event_id: AtomicU64::new(1),
+            history: Mutex::new(VecDeque::new()),
+            subscriptions: Mutex::new(HashSet::new()),
+            client_info: Mutex::new(None),
+            created_at: SystemTime::now(),
+            last_activity: Mutex::new(SystemTime::now()),
+            dropped: AtomicU64::new(0),
+            log_level: Mutex::new(None),
+            registry_notifications: true,
+        }
+    }
+
+    /// For a session that registry changes cannot be forwarded to, like
+    /// stdio proxying a server that it only polls.
+    pub fn without_registry_notifications(mut self) -> Self {
+        self.registry_notifications = false;
+        self
+    }
+
+    pub fn principal(&self) -> Principal {
+        self.principal.lock().unwrap().clone()
+    }
+
+    /// Fails with 403 unless `principal` is the caller that opened the
+    /// session, so a leaked session id does not hand it to someone else.
+    pub fn check_caller(&self, principal: &Principal) -> Result<(), ApiError> {
+        if self.principal.lock().unwrap().is_same_caller(principal) {
+            Ok(())
+        } else {
+            Err(ApiError::new(
+                ErrorCode::Forbidden,
+                format!("Session '{}' belongs to another caller", self.id),
+            ))
+        }
+    }
+
+    /// Takes the scopes and address of the caller's current request, e.g.
+    /// from a refreshed token, after `check_caller`.
+    pub fn set_caller(&self, principal: Principal, source_ip: Option<String>) {
+        *self.principal.lock().unwrap() = principal;
+        *self.source_ip.lock().unwrap() = source_ip;
+    }
+
+    /// The caller as recorded in the audit log for changes made by tools.
+    pub fn actor(&self) -> Actor {
+        let mut actor = Actor::new(&self.principal(), self.source_ip.lock().unwrap().clone());
+        actor.mcp_session = Some(self.id.clone());
+        actor.mcp_client = self
+            .client_info
+            .lock()
+            .unwrap()
+            .as_ref()
+            .and_then(|info| info.get("name")?.as_str().map(str::to_string));
+        actor
+    }
+
+    pub fn protocol_version(&self) -> Option<String> {
+        self.protocol_version.lock().unwrap().clone()
+    }
+
+    pub fn set_protocol_version(&self, version: String) {
+        *self.protocol_version.lock().unwrap() = Some(version);
+    }
+
+    pub fn tool_context(&self) -> ToolContext {
+        self.tool_context.lock().unwrap().clone()
+    }
+
+    pub fn write_access(&self) -> bool {
+        self.tool_context.lock().unwrap().write_access
+    }
+
+    /// Changes the tool context and, if that changes the visible tools,
+    /// sends `notifications/tools/list_changed`.
+    pub fn update_tool_context(&self, update: impl FnOnce(&mut ToolContext)) {
+        let mut ctx = self.tool_context.lock().unwrap();
+        let before: Vec<_> = tools::visible(&ctx).map(|t| t.name).collect();
+        update(&mut ctx);
+        let changed = !tools::visible(&ctx).map(|t| t.name).eq(before);
+        drop(ctx);
+        if changed {
+            let _ = self.send(notification("notifications/tools/list_changed", serde_json::json!({})));
+        }
+    }
+
+    pub fn set_client_info(&self, info: serde_json::Value) {
+        *self.client_info.lock().unwrap() = Some(info);
+    }
+
+    pub fn set_log_level(&self, level: LogLevel) {
+        *self.log_level.lock().unwrap() = Some(level);
+    }
+
+    /// Sends a `notifications/message` if the client asked for `level`.
+    pub fn log(&self, level: LogLevel, logger: &str, data: serde_json::Value) {
+        if self.log_level.lock().unwrap().is_some_and(|min| level >= min) {
+            let params = serde_json::json!({ "level": level, "logger": logger, "data": data });
+            let _ = self.send(notification("notifications/message", params));
+        }
+    }
+
+    /// Records client activity, postponing the idle timeout.
+    pub fn touch(&self) {
+        *self.last_activity.lock().unwrap() = SystemTime::now();
+    }
+
+    pub fn idle_for(&self) -> Duration {
+        self.last_activity.lock().unwrap().elapsed().unwrap_or_default()
+    }
+
+    /// Whether a server→client stream is attached and its client still
+    /// connected.
+    pub fn stream_open(&self) -> bool {
+        self.stream.lock().unwrap().as_ref().is_some_and(|tx| !tx.is_closed())
+    }
+
+    /// Attaches a new server→client stream, replacing any previous one.
+    pub fn attach(&self, tx: mpsc::Sender<sse::Event>) {
+        *self.stream.lock().unwrap() = Some(tx);
+    }
+
+    pub fn detach(&self) {
+        *self.stream.lock().unwrap() = None;
+    }
+
+    pub fn subscribe(&self, uri: &str) {
+        self.subscriptions.lock().unwrap().insert(uri.to_string());
+    }
+
+    pub fn unsubscribe(&self, uri: &str) {
+        self.subscriptions.lock().unwrap().remove(uri);
+    }
+
+    pub fn is_subscribed(&self, uri: &str) -> bool {
+        self.subscriptions.lock().unwrap().contains(uri)
+    }
+
+    /// Assigns the next event id to `json` and keeps it for replay.
+    pub fn record(&self, json: String) -> sse::Event {
+        let id = self.next_event_id.fetch_add(1, Ordering::Relaxed);
+        let mut history = self.history.lock().unwrap();
+        if history.len() == HISTORY_LIMIT {
+            history.pop_front();
+        }
+        history.push_back((id, json.clone()));
+        message_event(id, json)
+    }
+
+    /// Records `json` and delivers it on the attached stream without
+    /// waiting. Undelivered messages stay in the history, so a resuming
+    /// Streamable HTTP client still receives them.
+    pub fn send(&self, json: String) -> Result<(), SendError> {
+        let event = self.record(json);
+        let mut stream = self.stream.lock().unwrap();
+        let Some(tx) = stream.as_ref() else { return Err(SendError::NoStream) };
+        match tx.try_send(event) {
+            Ok(()) => Ok(()),
+            Err(mpsc::error::TrySendError::Full(_)) => {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+                Err(SendError::Full)
+            }
+            Err(mpsc::error::TrySendError::Closed(_)) => {
+                *stream = None;
+                Err(SendError::NoStream)
+            }
+        }
+    }
+
+    /// Messages recorded after `last_event_id`, oldest first.
+    pub fn replay_after(&self, last_event_id: u64) -> Vec<sse::Event> {
+        self.recorded_after(last_event_id)
+            .into_iter()
+            .map(|(id, json)| message_event(id, json))
+            .collect()
+    }
+
+    /// Messages recorded after `last_event_id` with their event ids, oldest
+    /// first, for transports without a stream to write them to.
+    pub fn recorded_after(&self, last_event_id: u64) -> Vec<(u64, String)> {
+        self.history
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(id, _)| *id > last_event_id)
+            .cloned()
+            .collect()
+    }
+}
+
+fn message_event(id: u64, json: String) -> sse::Event {
+    sse::Event::Data(sse::Data::new(json).event("message").id(id.to_string()))
+}
+
+/// Whether a connecting HTTP client may use the write tools: with API keys
+/// or JWTs, when `principal` may change some entities (each call is then
+/// checked against the entity's owners); otherwise only when
+/// `MCP_WRITE_TOKEN` is configured and presented as a bearer token.
+pub fn has_write_access(req: &HttpRequest, principal: &Principal, config: &Config) -> bool {
+    if config.auth_enabled() {
+        return principal.may_write();
+    }
+    let Some(token) = &config.mcp_write_token else { return false };
+    req.headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .is_some_and(|presented| presented == token)
+}
+
+/// Rejects browser requests from origins other than localhost,
+/// `PUBLIC_BASE_URL` and `MCP_ALLOWED_ORIGINS`, so a web page cannot reach
+/// a local server through DNS rebinding. Requests without an `Origin` do
+/// not come from a browser page and pass.
+pub fn check_origin(req: &HttpRequest, config: &Config) -> Result<(), ApiError> {
+    let Some(origin) = req.headers().get(header::ORIGIN) else { return Ok(()) };
+    let origin = origin.to_str().unwrap_or_default().trim_end_matches('/').to_lowercase();
+    let host = origin.split_once("://").map_or("", |(_, rest)| rest);
+    let hostname = match host.strip_prefix('[') {
+        Some(ipv6) => ipv6.split(']').next().unwrap_or_default(),
+        None => host.split(':').next().unwrap_or_default(),
+    };
+    let allowed = matches!(hostname, "localhost" | "127.0.0.1" | "::1")
+        || config.public_base_url.as_ref().is_some_and(|base| base.eq_ignore_ascii_case(&origin))
+        || config.mcp_allowed_origins.iter().any(|allowed| allowed == "*" || *allowed == origin);
+    if allowed {
+        Ok(())
+    } else {
+        Err(ApiError::new(ErrorCode::Forbidden, format!("Origin '{origin}' may not use the MCP endpoints")))
+    }
+}
+
+pub type Sessions = Arc<RwLock<HashMap<String, Arc<Session>>>>;
+
+pub fn new_sessions() -> Sessions {
+    Arc::new(RwLock::new(HashMap::new()))
+}
+
+/// Adds `session` under its id, unless `MCP_MAX_SESSIONS` are open.
+pub async fn register(sessions: &Sessions, session: Arc<Session>, config: &Config) -> Result<String, ApiError> {
+    let mut sessions = sessions.write().await;
+    if sessions.len() >= config.mcp_max_sessions {
+        return Err(ApiError::new(
+            ErrorCode::TooManySessions,
+            format!("The server already has {} MCP sessions open", sessions.len()),
+        ));
+    }
+    let session_id = session.id.clone();
+    sessions.insert(session_id.clone(), session);
+    Ok(session_id)
+}
+
+/// Whether a session should be closed: its legacy SSE stream is gone, or
+/// it has no open stream and has been idle longer than `idle_timeout`.
+fn expired(session: &Session, idle_timeout: Duration) -> bool {
+    if session.stream_open() {
+        return false;
+    }
+    session.transport == Transport::Sse || session.idle_for() > idle_timeout
+}
+
+/// Periodically closes sessions whose clients went away without ending them.
+pub fn spawn_session_reaper(sessions: Sessions, idle_timeout: Duration) {
+    let interval = (idle_timeout / 4).clamp(Duration::from_secs(1), Duration::from_secs(60));
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(interval).await;
+            sessions.write().await.retain(|_, session| !expired(session, idle_timeout));
+        }
+    });
+}
+
+/// Ends every session and its stream, so open SSE connections complete and
+/// the server can shut down without waiting for them.
+pub async fn drain(sessions: &Sessions) {
+    let mut sessions = sessions.write().await;
+    for session in sessions.values() {
+        session.detach();
+    }
+    sessions.clear();
+}
+
+// ── Server notifications ─────────────────────────────────────────
+
+fn notification(method: &str, params: serde_json::Value) -> String {
+    serde_json::json!({ "jsonrpc": "2.0", "method": method, "params": params }).to_string()
+}
+
+/// Forwards registry changes to MCP sessions until `changes` closes:
+/// `notifications/resources/updated` to sessions subscribed to the entity,
+/// `notifications/resources/list_changed` to every session when an entity
+/// is created or deleted, and an `info` log message describing the change.
+/// Messages that don't fit a session's stream buffer are dropped; they
+/// remain in its history for replay.
+pub fn spawn_change_notifier(mut changes: broadcast::Receiver<ChangeEvent>, sessions: Sessions) {
+    tokio::spawn(async move {
+        loop {
+            let event = match changes.recv().await {
+                Ok(event) => Some(event),
+                // Missed events: tell everyone to re-list.
+                Err(RecvError::Lagged(_)) => None,
+                Err(RecvError::Closed) => break,
+            };
+            let targets: Vec<_> = sessions.read().await.values().cloned().collect();
+            for session in targets {
+                notify_change(&session, event.as_ref());
+            }
+        }
+    });
+}
+
+/// Sends `session` the notifications for registry change `event`, or for
+/// changes that were missed if `None`.
+pub fn notify_change(session: &Session, event: Option<&ChangeEvent>) {
+    if let Some(uri) = event.map(|e| resources::uri(e.kind, &e.key)).filter(|u| session.is_subscribed(u)) {
+        let params = serde_json::json!({ "uri": uri });
+        let _ = session.send(notification("notifications/resources/updated", params));
+    }
+    if event.is_none_or(|e| e.action != ChangeAction::Updated) {
+        let params = serde_json::json!({});
+        let _ = session.send(notification("notifications/resources/list_changed", params));
+    }
+    if let Some(event) = event {
+        let action = match event.action {
+            ChangeAction::Created => "created",
+            ChangeAction::Updated => "updated",
+            ChangeAction::Deleted => "deleted",
+        };
+        let message = format!("{} '{}' {action}", event.kind.label(), event.key);
+        let change = serde_json::json!({ "kind": event.kind, "key": event.key, "action": event.action });
+        session.log(LogLevel::Info, "registry", serde_json::json!({ "message": message, "change": change }));
+    }
+}
+
+/// A live session as listed by the admin API.
+#[derive(Serialize)]
+pub struct SessionInfo {
+    pub id: String,
+    pub transport: Transport,
+    /// Name of the API key or JWT subject that opened the session.
+    pub principal: String,
+    pub protocol_version: Option<String>,
+    pub client_info: Option<serde_json::Value>,
+    pub write_access: bool,
+    pub stream_open: bool,
+    pub subscriptions: usize,
+    pub dropped_messages: u64,
+    pub age_secs: u64,
+    pub idle_secs: u64,
+}
+
+impl SessionInfo {
+    pub fn new(id: &str, session: &Session) -> Self {
+        Self {
+            id: id.to_string(),
+            transport: session.transport,
+            principal: session.principal().name,
+            protocol_version: session.protocol_version(),
+            client_info: session.client_info.lock().unwrap().clone(),
+            write_access: session.write_access(),
+            stream_open: session.stream_open(),
+            subscriptions: session.subscriptions.lock().unwrap().len(),
+            dropped_messages: session.dropped.load(Ordering::Relaxed),
+            age_secs: session.created_at.elapsed().unwrap_or_default().as_secs(),
+            idle_secs: session.idle_for().as_secs(),
+        }
+    }
+}
+
+// ── JSON-RPC types ───────────────────────────────────────────────
+
+/// A validated request or notification. Build with `parse_request`.
+struct JsonRpcRequest {
+    /// `None` for notifications; a present `"id": null` is `Some(Null)`.
+    id: Option<serde_json::Value>,
+    method: String,
+    params: serde_json::Value,
+}
+
+fn invalid_request(id: serde_json::Value, message: &str) -> JsonRpcResponse {
+    JsonRpcResponse::error(id, -32600, format!("Invalid Request: {message}"))
+}
+
+/// Checks that `value` is a JSON-RPC 2.0 request object. Responses sent by
+/// the client (it never gets requests from us) yield `Ok(None)`.
+fn parse_request(value: serde_json::Value) -> Result<Option<JsonRpcRequest>, JsonRpcResponse> {
+    let serde_json::Value::Object(mut obj) = value else {
+        return Err(invalid_request(serde_json::Value::Null, "expected an object"));
+    };
+    let id = obj.remove("id");
+    let reply_id = match &id {
+        Some(id @ (serde_json::Value::String(_) | serde_json::Value::Number(_) | serde_json::Value::Null)) => id.clone(),
+        Some(_) => return Err(invalid_request(serde_json::Value::Null, "'id' must be a string, number or null")),
+        None => serde_json::Value::Null,
+    };
+    if obj.get("jsonrpc").and_then(|v| v.as_str()) != Some("2.0") {
+        return Err(invalid_request(reply_id, "'jsonrpc' must be \"2.0\""));
+    }
+    let method = match obj.remove("method") {
+        Some(serde_json::Value::String(method)) => method,
+        None if obj.contains_key("result") || obj.contains_key("error") => return Ok(None),
+        _ => return Err(invalid_request(reply_id, "'method' must be a string")),
+    };
+    let params = match obj.remove("params") {
+        None => serde_json::Value::Null,
+        Some(params @ (serde_json::Value::Object(_) | serde_json::Value::Array(_))) => params,
+        Some(_) => return Err(invalid_request(reply_id, "'params' must be an object or array")),
+    };
+    Ok(Some(JsonRpcRequest { id, method, params }))
+}
+
+#[derive(Serialize)]
+struct JsonRpcResponse {
+    jsonrpc: String,
+    id: serde_json::Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<JsonRpcError>,
+}
+
+#[derive(Serialize)]
+struct JsonRpcError {
+    code: i64,
+    message: String,
+}
+
+impl JsonRpcResponse {
+    fn success(id: serde_json::Value, result: serde_json::Value) -> Self {
+        Self {
+            jsonrpc: "2.0".into(),
+            id,
+            result: Some(result),
+            error: None,
+        }
+    }
+
+    fn error(id: serde_json::Value, code: i64, message: String) -> Self {
+        Self {
+            jsonrpc: "2.0".into(),
+            id,
+            result: None,
+            error: Some(JsonRpcError { code, message }),
+        }
+    }
+}
+
+// ── Method dispatch ──────────────────────────────────────────────
+
+/// Handles one raw JSON-RPC message for transports without HTTP framing.
+/// Returns the serialized response, or `None` for notifications.
+pub fn handle_message(session: &Session, message: &str, state: &AppState) -> Option<String> {
+    match serde_json::from_str(message) {
+        Ok(value) => process(session, value, state),
+        Err(e) => Some(parse_error(e)),
+    }
+}
+
+/// The serialized `-32700` response for a body that is not JSON.
+fn parse_error(e: serde_json::Error) -> String {
+    let response = JsonRpcResponse::error(serde_json::Value::Null, -32700, format!("Parse error: {e}"));
+    serde_json::to_string(&response).unwrap()
+}
+
+/// Whether `value` is a single `initialize` request, which starts a session.
+fn is_initialize(value: &serde_json::Value) -> bool {
+    value.get("method").and_then(|m| m.as_str()) == Some("initialize")
+}
+
+/// Handles a parsed message: a single request or notification, or a batch.
+/// Returns the serialized response (an array for batches), or `None` when
+/// nothing needs an answer.
+fn process(session: &Session, value: serde_json::Value, state: &AppState) -> Option<String> {
+    let serde_json::Value::Array(items) = value else {
+        let response = process_one(session, value, state)?;
+        return Some(serde_json::to_string(&response).unwrap());
+    };
+    if items.is_empty() {
+        let response = invalid_request(serde_json::Value::Null, "empty batch");
+        return Some(serde_json::to_string(&response).unwrap());
+    }
+    let responses: Vec<JsonRpcResponse> = items
+        .into_iter()
+        .filter_map(|item| {
+            if is_initialize(&item) {
+                let id = item.get("id").cloned().unwrap_or_default();
+                return Some(invalid_request(id, "initialize must not be part of a batch"));
+            }
+            process_one(session, item, state)
+        })
+        .collect();
+    (!responses.is_empty()).then(|| serde_json::to_string(&responses).unwrap())
+}
+
+fn process_one(session: &Session, value: serde_json::Value, state: &AppState) -> Option<JsonRpcResponse> {
+    match parse_request(value) {
+        Ok(req) => dispatch(session, req?, state),
+        Err(response) => Some(response),
+    }
+}
+
+/// Runs one request on behalf of `session`. Notifications (no `id`) get no
+/// response.
+fn dispatch(session: &Session, req: JsonRpcRequest, state: &AppState) -> Option<JsonRpcResponse> {
+    session.touch();
+    let id = req.id?;
+    let response = handle_method(&req.method, &req.params, session, state, id);
+    if req.method == "initialize" {
+        if let Some(info) = req.params.get("clientInfo") {
+            session.set_client_info(info.clone());
+        }
+        if let Some(version) = response
+            .result
+            .as_ref()
+            .and_then(|r| r.get("protocolVersion"))
+            .and_then(|v| v.as_str())
+        {
+            session.set_protocol_version(version.to_string());
+        }
+    }
+    Some(response)
+}
+
+fn handle_method(
+    method: &str,
+    params: &serde_json::Value,
+    session: &Session,
+    state: &AppState,
+    id: serde_json::Value,
+) -> JsonRpcResponse {
+    match method {
+        "initialize" => handle_initialize(params, session, id),
+        "ping" => JsonRpcResponse::success(id, serde_json::json!({})),
+        "logging/setLevel" => handle_set_level(params, session, id),
+        "completion/complete" => match completion::complete(params, state) {
+            Ok(result) => JsonRpcResponse::success(id, result),
+            Err(message) => JsonRpcResponse::error(id, -32602, message),
+        },
+        "tools/list" => handle_tools_list(session, id),
+        "tools/call" => handle_tools_call(params, session, state, id),
+        "prompts/list" => JsonRpcResponse::success(id, prompts::list()),
+        "prompts/get" => handle_prompts_get(params, state, id),
+        "resources/list" => JsonRpcResponse::success(id, resources::list(state)),
+        "resources/templates/list" => JsonRpcResponse::success(id, resources::templates()),
+        "resources/read" => handle_resources_read(params, state, id),
+        "resources/subscribe" | "resources/unsubscribe" => handle_resources_subscription(method, params, session, id),
+        _ => JsonRpcResponse::error(id, -32601, format!("Method not found: {method}")),
+    }
+}
+
+/// The protocol version to use for a client requesting `requested`: the
+/// same version if supported, otherwise the newest one we speak.
+pub fn negotiate_protocol_version(requested: Option<&str>) -> &'static str {
+    PROTOCOL_VERSIONS
+        .iter()
+        .find(|v| Some(**v) == requested)
+        .unwrap_or(&PROTOCOL_VERSIONS[0])
+}
+
+fn handle_initialize(params: &serde_json::Value, session: &Session, id: serde_json::Value) -> JsonRpcResponse {
+    let requested = params.get("protocolVersion").and_then(|v| v.as_str());
+    let registry_notifications = session.registry_notifications;
+    JsonRpcResponse::success(
+        id,
+        serde_json::json!({
+            "protocolVersion": negotiate_protocol_version(requested),
+            "capabilities": {
+                "tools": {
+                    "listChanged": true
+                },
+                "prompts": {},
+                "logging": {},
+                "completions": {},
+                "resources": {
+                    "subscribe": registry_notifications,
+                    "listChanged": registry_notifications
+                }
+            },
+            "serverInfo": {
+                "name": "codegang-datasource",
+                "version": "0.1.0"
+            }
+        }),
+    )
+}
+
+fn handle_set_level(params: &serde_json::Value, session: &Session, id: serde_json::Value) -> JsonRpcResponse {
+    match params.get("level").cloned().map(serde_json::from_value::<LogLevel>) {
+        Some(Ok(level)) => {
+            session.set_log_level(level);
+            JsonRpcResponse::success(id, serde_json::json!({}))
+        }
+        _ => JsonRpcResponse::error(id, -32602, "Invalid params: expected a syslog 'level'".to_string()),
+    }
+}
+
+fn handle_prompts_get(params: &serde_json::Value, state: &AppState, id: serde_json::Value) -> JsonRpcResponse {
+    let name = params.get("name").and_then(|v| v.as_str()).unwrap_or("");
+    let args = params.get("arguments").cloned().unwrap_or(serde_json::json!({}));
+    match prompts::get(name, &args, state) {
+        Ok(result) => JsonRpcResponse::success(id, result),
+        Err(message) => JsonRpcResponse::error(id, -32602, message),
+    }
+}
+
+fn handle_resources_read(params: &serde_json::Value, state: &AppState, id: serde_json::Value) -> JsonRpcResponse {
+    let uri = params.get("uri").and_then(|v| v.as_str()).unwrap_or("");
+    match resources::read(state, uri) {
+        Some(result) => JsonRpcResponse::success(id, result),
+        None => JsonRpcResponse::error(id, -32002, format!("Resource not found: {uri}")),
+    }
+}
+
+fn handle_resources_subscription(
+    method: &str,
+    params: &serde_json::Value,
+    session: &Session,
+    id: serde_json::Value,
+) -> JsonRpcResponse {
+    let uri = params.get("uri").and_then(|v| v.as_str()).unwrap_or("");
+    // Entities that do not exist yet may be subscribed to; the first
+    // notification then announces their creation.
+    if resources::parse_uri(uri).is_none() {
+        return JsonRpcResponse::error(id, -32602, format!("Invalid resource URI: {uri}"));
+    }
+    if method == "resources/subscribe" {
+        session.subscribe(uri);
+    } else {
+        session.unsubscribe(uri);
+    }
+    JsonRpcResponse::success(id, serde_json::json!({}))
+}
+
+fn handle_tools_list(session: &Session, id: serde_json::Value) -> JsonRpcResponse {
+    JsonRpcResponse::success(id, serde_json::json!({ "tools": tools::list(&session.tool_context()) }))
+}
+
+fn handle_tools_call(
+    params: &serde_json::Value,
+    session: &Session,
+    state: &AppState,
+    id: serde_json::Value,
+) -> JsonRpcResponse {
+    let tool_name = params
+        .get("name")
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+
+    let args = params
+        .get("arguments")
+        .cloned()
+        .unwrap_or(serde_json::json!({}));
+
+    let Some(tool) = tools::find(tool_name) else {
+        return JsonRpcResponse::error(id, -32602, format!("Unknown tool: {tool_name}"));
+    };
+
+    let outcome = if tool.write && !session.write_access() {
+        Err("Write tools are not enabled for this session".to_string())
+    } else if !tool.available(&session.tool_context()) {
+        Err(format!("{tool_name} needs a workspace; call select_workspace first"))
+    } else {
+        tool.call(args, state, session)
+    };
+    state.metrics().observe_tool_call(tool_name, outcome.is_err());
+    if let Err(message) = &outcome {
+        session.log(LogLevel::Error, "tools", serde_json::json!({ "tool": tool_name, "message": message }));
+    }
+    JsonRpcResponse::success(id, tool_result(outcome))
+}
+
+/// A `tools/call` result: structured output, mirrored as compact JSON text
+/// for clients without `structuredContent` support, or an error message.
+pub fn tool_result(outcome: Result<serde_json::Value, String>) -> serde_json::Value {
+    match outcome {
+        Ok(output) => serde_json::json!({
+            "content": [{ "type": "text", "text": output.to_string() }],
+            "structuredContent": output,
+            "isError": false
+        }),
+        Err(message) => serde_json::json!({
+            "content": [{ "type": "text", "text": message }],
+            "isError": true
+        }),
+    }
+}
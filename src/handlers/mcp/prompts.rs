@@ -0,0 +1,288 @@
+//! Curated MCP prompt templates. `prompts/get` fills them in from the live
+//! registry, embedding the relevant entities as resources.
+
+use super::{resources, views};
+use crate::model::{Datasource, EntityKind, MessageSchema, ServiceDefinition};
+use crate::storage::AppState;
+
+fn argument(name: &str, description: &str) -> serde_json::Value {
+    serde_json::json!({ "name": name, "description": description, "required": true })
+}
+
+/// Result of `prompts/list`.
+pub fn list() -> serde_json::Value {
+    serde_json::json!({
+        "prompts": [
+            {
+                "name": "implement_queue_consumer",
+                "title": "Implement a queue consumer",
+                "description": "Implement a consumer for a service-bus topic, given its contract, JSON Schema and current publishers.",
+                "arguments": [argument("topic", "Topic name, e.g. 'user-registered'")]
+            },
+            {
+                "name": "add_grpc_client",
+                "title": "Add a gRPC client",
+                "description": "Add a gRPC client for a proto contract to a service.",
+                "arguments": [
+                    argument("service", "Service that will call the contract, e.g. 'contest-engine-grpc'"),
+                    argument("contract", "Proto contract name, e.g. 'UsersGrpcService'")
+                ]
+            },
+            {
+                "name": "onboard_to_service",
+                "title": "Onboard to a service",
+                "description": "Explain a service together with everything it talks to: its contracts and the services on the other side.",
+                "arguments": [argument("service", "Service name, e.g. 'contest-engine-grpc'")]
+            }
+        ]
+    })
+}
+
+fn text_message(text: String) -> serde_json::Value {
+    serde_json::json!({ "role": "user", "content": { "type": "text", "text": text } })
+}
+
+/// A message embedding the resource for `kind`/`key`, if it exists.
+fn resource_message(state: &AppState, kind: EntityKind, key: &str) -> Option<serde_json::Value> {
+    let read = resources::read(state, &resources::uri(kind, key))?;
+    let contents = read["contents"][0].clone();
+    Some(serde_json::json!({ "role": "user", "content": { "type": "resource", "resource": contents } }))
+}
+
+/// A message embedding a summary of `neighbour` as seen from `svc`: its
+/// type and description, and the topics and proto contracts they share.
+fn neighbour_message(svc: &ServiceDefinition, neighbour: &ServiceDefinition) -> serde_json::Value {
+    let published = |s: &ServiceDefinition| s.queue.as_ref().and_then(|q| q.publish_queues.clone());
+    let subscribed = |s: &ServiceDefinition| s.queue.as_ref().and_then(|q| q.subscribe_queues.clone());
+
+    let mut summary = serde_json::to_value(views::summarize_service(neighbour)).unwrap();
+    summary["shared_queues"] = union(
+        shared(&published(svc), &subscribed(neighbour)),
+        shared(&subscribed(svc), &published(neighbour)),
+    )
+    .into();
+    summary["shared_proto_contracts"] = union(
+        shared(&svc.grpc_servers, &neighbour.grpc_clients),
+        shared(&svc.grpc_clients, &neighbour.grpc_servers),
+    )
+    .into();
+    let contents = serde_json::json!({
+        "uri": resources::uri(EntityKind::Service, &neighbour.name),
+        "mimeType": "application/json",
+        "text": serde_json::to_string_pretty(&summary).unwrap()
+    });
+    serde_json::json!({ "role": "user", "content": { "type": "resource", "resource": contents } })
+}
+
+fn required_arg<'a>(args: &'a serde_json::Value, name: &str) -> Result<&'a str, String> {
+    args.get(name)
+        .and_then(|v| v.as_str())
+        .filter(|v| !v.is_empty())
+        .ok_or_else(|| format!("Missing required argument '{name}'"))
+}
+
+fn names(services: &[&ServiceDefinition]) -> String {
+    if services.is_empty() {
+        "none".to_string()
+    } else {
+        services.iter().map(|s| s.name.as_str()).collect::<Vec<_>>().join(", ")
+    }
+}
+
+fn contains(list: &Option<Vec<String>>, key: &str) -> bool {
+    list.as_ref().is_some_and(|l| l.iter().any(|i| i == key))
+}
+
+/// The keys in `ours` that `theirs` lists too.
+fn shared(ours: &Option<Vec<String>>, theirs: &Option<Vec<String>>) -> Vec<String> {
+    ours.iter().flatten().filter(|k| contains(theirs, k)).cloned().collect()
+}
+
+fn union(mut a: Vec<String>, b: Vec<String>) -> Vec<String> {
+    for key in b {
+        if !a.contains(&key) {
+            a.push(key);
+        }
+    }
+    a
+}
+
+fn publishes(s: &ServiceDefinition, topic: &str) -> bool {
+    s.queue.as_ref().is_some_and(|q| contains(&q.publish_queues, topic))
+}
+
+fn subscribes(s: &ServiceDefinition, topic: &str) -> bool {
+    s.queue.as_ref().is_some_and(|q| contains(&q.subscribe_queues, topic))
+}
+
+fn services_where(ds: &Datasource, pred: impl Fn(&ServiceDefinition) -> bool) -> Vec<&ServiceDefinition> {
+    ds.services.iter().filter(|s| pred(s)).collect()
+}
+
+/// Result of `prompts/get`. `Err` carries the message for an invalid-params
+/// error.
+pub fn get(name: &str, args: &serde_json::Value, state: &AppState) -> Result<serde_json::Value, String> {
+    let ds = state.get_datasource();
+    let (description, messages) = match name {
+        "implement_queue_consumer" => {
+            let topic = required_arg(args, "topic")?;
+            let contract = ds
+                .queue_contracts
+                .iter()
+                .find(|q| q.topic_name == topic)
+                .ok_or_else(|| format!("Queue contract '{topic}' not found"))?;
+            let publishers = services_where(&ds, |s| publishes(s, topic));
+            let subscribers = services_where(&ds, |s| subscribes(s, topic));
+            let mut text = format!(
+                "Implement a consumer for the service-bus topic '{topic}'.\n\n\
+                 Publishers: {}\nExisting subscribers: {}\n\n\
+                 Deserialize messages according to the contract below, validate them against the JSON Schema, \
+                 and handle redelivery idempotently.",
+                names(&publishers),
+                names(&subscribers)
+            );
+            if let Some(schema) = &contract.message_schema {
+                let json_schema = serde_json::to_string_pretty(&message_json_schema(schema)).unwrap();
+                text.push_str(&format!("\n\nJSON Schema for {}:\n{json_schema}", schema.name));
+            }
+            let mut messages = vec![text_message(text)];
+            messages.extend(resource_message(state, EntityKind::QueueContract, topic));
+            (format!("Implement a consumer for topic '{topic}'"), messages)
+        }
+        "add_grpc_client" => {
+            let service = required_arg(args, "service")?;
+            let contract = required_arg(args, "contract")?;
+            let svc = ds
+                .services
+                .iter()
+                .find(|s| s.name == service)
+                .ok_or_else(|| format!("Service '{service}' not found"))?;
+            if !ds.proto_contracts.iter().any(|p| p.name == contract) {
+                return Err(format!("Proto contract '{contract}' not found"));
+            }
+            let servers = services_where(&ds, |s| contains(&s.grpc_servers, contract));
+            let mut text = format!(
+                "Add a gRPC client for '{contract}' to the service '{service}'.\n\n\
+                 Services serving {contract}: {}\n\n\
+                 Generate the client from the .proto below, make the endpoint configurable, \
+                 and add '{contract}' to the service's grpc_clients in the registry.",
+                names(&servers)
+            );
+            if contains(&svc.grpc_clients, contract) {
+                text.push_str(&format!("\n\nNote: '{service}' already lists '{contract}' in grpc_clients."));
+            }
+            let mut messages = vec![text_message(text)];
+            messages.extend(resource_message(state, EntityKind::Service, service));
+            messages.extend(resource_message(state, EntityKind::ProtoContract, contract));
+            (format!("Add a {contract} client to '{service}'"), messages)
+        }
+        "onboard_to_service" => {
+            let service = required_arg(args, "service")?;
+            let svc = ds
+                .services
+                .iter()
+                .find(|s| s.name == service)
+                .ok_or_else(|| format!("Service '{service}' not found"))?;
+            let servers = svc.grpc_servers.clone().unwrap_or_default();
+            let clients = svc.grpc_clients.clone().unwrap_or_default();
+            let published = svc.queue.as_ref().and_then(|q| q.publish_queues.clone()).unwrap_or_default();
+            let subscribed = svc.queue.as_ref().and_then(|q| q.subscribe_queues.clone()).unwrap_or_default();
+
+            let callers = services_where(&ds, |s| {
+                s.name != service && servers.iter().any(|c| contains(&s.grpc_clients, c))
+            });
+            let callees = services_where(&ds, |s| {
+                s.name != service && clients.iter().any(|c| contains(&s.grpc_servers, c))
+            });
+            let consumers = services_where(&ds, |s| {
+                s.name != service && published.iter().any(|t| subscribes(s, t))
+            });
+            let producers = services_where(&ds, |s| {
+                s.name != service && subscribed.iter().any(|t| publishes(s, t))
+            });
+
+            let text = format!(
+                "Help me onboard to the service '{service}'. Explain what it does, how it fits into the system \
+                 and where to start reading, using the registry entries below.\n\n\
+                 Called over gRPC by: {}\nCalls over gRPC: {}\n\
+                 Consumers of its topics: {}\nProducers of topics it consumes: {}",
+                names(&callers),
+                names(&callees),
+                names(&consumers),
+                names(&producers)
+            );
+            let mut messages = vec![text_message(text)];
+            messages.extend(resource_message(state, EntityKind::Service, service));
+            for contract in servers.iter().chain(&clients) {
+                messages.extend(resource_message(state, EntityKind::ProtoContract, contract));
+            }
+            for topic in published.iter().chain(&subscribed) {
+                messages.extend(resource_message(state, EntityKind::QueueContract, topic));
+            }
+            let mut neighbours: Vec<&ServiceDefinition> = Vec::new();
+            for neighbour in callers.iter().chain(&callees).chain(&consumers).chain(&producers) {
+                if !neighbours.iter().any(|n| n.name == neighbour.name) {
+                    neighbours.push(neighbour);
+                }
+            }
+            for neighbour in neighbours {
+                messages.push(neighbour_message(svc, neighbour));
+            }
+            (format!("Onboard to '{service}'"), messages)
+        }
+        _ => return Err(format!("Unknown prompt: {name}")),
+    };
+    Ok(serde_json::json!({ "description": description, "messages": messages }))
+}
+
+/// Translates a language-agnostic `MessageSchema` into JSON Schema.
+fn message_json_schema(schema: &MessageSchema) -> serde_json::Value {
+    let mut properties = serde_json::Map::new();
+    let mut required = Vec::new();
+    for field in &schema.fields {
+        let (mut property, optional) = match field.field_type.strip_prefix("optional<").and_then(|t| t.strip_suffix('>')) {
+            Some(inner) => (field_json_schema(inner), true),
+            None => (field_json_schema(&field.field_type), false),
+        };
+        if let Some(description) = &field.description {
+            property["description"] = description.as_str().into();
+        }
+        properties.insert(field.name.clone(), property);
+        if !optional {
+            required.push(field.name.clone());
+        }
+    }
+    serde_json::json!({
+        "title": schema.name,
+        "type": "object",
+        "properties": properties,
+        "required": required
+    })
+}
+
+fn field_json_schema(field_type: &str) -> serde_json::Value {
+    let generic = |prefix: &str| field_type.strip_prefix(prefix).and_then(|t| t.strip_suffix('>'));
+    if let Some(inner) = generic("repeated<") {
+        return serde_json::json!({ "type": "array", "items": field_json_schema(inner) });
+    }
+    if let Some(inner) = generic("optional<") {
+        return field_json_schema(inner);
+    }
+    if let Some((_, value)) = generic("map<").and_then(|kv| kv.split_once(',')) {
+        return serde_json::json!({ "type": "object", "additionalProperties": field_json_schema(value.trim()) });
+    }
+    if let Some(variants) = field_type.strip_prefix("enum(").and_then(|t| t.strip_suffix(')')) {
+        return serde_json::json!({ "type": "string", "enum": variants.split('|').map(str::trim).collect::<Vec<_>>() });
+    }
+    match field_type {
+        "string" => serde_json::json!({ "type": "string" }),
+        "i32" | "i64" | "u32" | "u64" => serde_json::json!({ "type": "integer" }),
+        "f32" | "f64" => serde_json::json!({ "type": "number" }),
+        "bool" => serde_json::json!({ "type": "boolean" }),
+        "uuid" => serde_json::json!({ "type": "string", "format": "uuid" }),
+        "datetime" => serde_json::json!({ "type": "string", "format": "date-time" }),
+        "bytes" => serde_json::json!({ "type": "string", "contentEncoding": "base64" }),
+        // Unknown or nested message types: accept anything.
+        _ => serde_json::json!({}),
+    }
+}
@@ -0,0 +1,110 @@
+//! Registry entities as MCP resources. Every entity has a `codegang://`
+//! URI; sessions can subscribe to URIs and are notified when the entity
+//! changes, whichever API made the change.
+
+use crate::model::EntityKind;
+use crate::storage::AppState;
+
+const SCHEME: &str = "codegang://";
+
+fn segment(kind: EntityKind) -> &'static str {
+    match kind {
+        EntityKind::Service => "services",
+        EntityKind::QueueContract => "queues",
+        EntityKind::NosqlContract => "nosql",
+        EntityKind::ProtoContract => "proto",
+    }
+}
+
+const KINDS: [EntityKind; 4] = [
+    EntityKind::Service,
+    EntityKind::QueueContract,
+    EntityKind::NosqlContract,
+    EntityKind::ProtoContract,
+];
+
+pub fn uri(kind: EntityKind, key: &str) -> String {
+    format!("{SCHEME}{}/{key}", segment(kind))
+}
+
+/// Splits a resource URI into entity kind and key.
+pub fn parse_uri(uri: &str) -> Option<(EntityKind, &str)> {
+    let (kind, key) = uri.strip_prefix(SCHEME)?.split_once('/')?;
+    let kind = KINDS.into_iter().find(|k| segment(*k) == kind)?;
+    (!key.is_empty()).then_some((kind, key))
+}
+
+/// Proto contracts are served as their raw `.proto` text; everything else
+/// as JSON.
+fn mime_type(kind: EntityKind) -> &'static str {
+    match kind {
+        EntityKind::ProtoContract => "text/x-protobuf",
+        _ => "application/json",
+    }
+}
+
+fn resource(kind: EntityKind, key: &str, description: Option<&str>) -> serde_json::Value {
+    let mut resource = serde_json::json!({
+        "uri": uri(kind, key),
+        "name": key,
+        "title": format!("{} {key}", kind.label()),
+        "mimeType": mime_type(kind)
+    });
+    if let Some(description) = description {
+        resource["description"] = description.into();
+    }
+    resource
+}
+
+/// Result of `resources/list`: one resource per entity.
+pub fn list(state: &AppState) -> serde_json::Value {
+    let ds = state.get_datasource();
+    let mut resources = Vec::new();
+    for s in &ds.services {
+        resources.push(resource(EntityKind::Service, &s.name, s.description.as_deref()));
+    }
+    for q in &ds.queue_contracts {
+        resources.push(resource(EntityKind::QueueContract, &q.topic_name, q.description.as_deref()));
+    }
+    for n in &ds.nosql_contracts {
+        resources.push(resource(EntityKind::NosqlContract, &n.entity_name, n.description.as_deref()));
+    }
+    for p in &ds.proto_contracts {
+        resources.push(resource(EntityKind::ProtoContract, &p.name, None));
+    }
+    serde_json::json!({ "resources": resources })
+}
+
+/// Result of `resources/templates/list`.
+pub fn templates() -> serde_json::Value {
+    let template = |kind: EntityKind, var: &str, description: &str| {
+        serde_json::json!({
+            "uriTemplate": format!("{SCHEME}{}/{{{var}}}", segment(kind)),
+            "name": segment(kind),
+            "description": description,
+            "mimeType": mime_type(kind)
+        })
+    };
+    serde_json::json!({
+        "resourceTemplates": [
+            template(EntityKind::Service, "name", "A microservice definition by name."),
+            template(EntityKind::QueueContract, "topic", "A queue/topic contract by topic name."),
+            template(EntityKind::NosqlContract, "entity", "A NoSQL entity contract by entity name."),
+            template(EntityKind::ProtoContract, "name", "The raw .proto text of a protobuf/gRPC contract."),
+        ]
+    })
+}
+
+/// Result of `resources/read`, or `None` if the entity does not exist.
+pub fn read(state: &AppState, uri: &str) -> Option<serde_json::Value> {
+    let (kind, key) = parse_uri(uri)?;
+    let text = match kind {
+        EntityKind::Service => serde_json::to_string_pretty(&state.get_service(key)?).unwrap(),
+        EntityKind::QueueContract => serde_json::to_string_pretty(&state.get_queue_contract(key)?).unwrap(),
+        EntityKind::NosqlContract => serde_json::to_string_pretty(&state.get_nosql_contract(key)?).unwrap(),
+        EntityKind::ProtoContract => state.get_proto_contract(key)?.raw_proto,
+    };
+    Some(serde_json::json!({
+        "contents": [{ "uri": uri, "mimeType": mime_type(kind), "text": text }]
+    }))
+}
@@ -0,0 +1,192 @@
+//! The Streamable HTTP transport (protocol 2025-03-26 and later) on a
+//! single `/mcp` endpoint: POST carries client messages and answers with
+//! JSON or a short SSE stream, GET opens a stream for server-initiated
+//! messages (resumable with `Last-Event-ID`), DELETE ends the session.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use actix_web::http::header;
+use actix_web::{web, HttpRequest, HttpResponse};
+use actix_web_lab::sse;
+use tokio::sync::mpsc;
+
+use super::{
+    check_origin, has_write_access, is_initialize, parse_error, process, register, Session, Sessions, Transport, PROTOCOL_VERSIONS,
+    STREAM_BUFFER,
+};
+use crate::audit::source_ip;
+use crate::auth::Principal;
+use crate::config::Config;
+use crate::error::{ApiError, ErrorCode};
+use crate::storage::AppState;
+
+pub const SESSION_HEADER: &str = "Mcp-Session-Id";
+const PROTOCOL_HEADER: &str = "MCP-Protocol-Version";
+
+fn header_value<'a>(req: &'a HttpRequest, name: &str) -> Option<&'a str> {
+    req.headers().get(name).and_then(|h| h.to_str().ok())
+}
+
+/// Looks up the session named by the `Mcp-Session-Id` header, checks that
+/// `principal` opened it and checks the `MCP-Protocol-Version` header, when
+/// present, against what we speak and what was negotiated in `initialize`.
+async fn find_session(
+    req: &HttpRequest,
+    principal: &Principal,
+    sessions: &Sessions,
+) -> Result<(String, Arc<Session>), ApiError> {
+    let session_id = header_value(req, SESSION_HEADER)
+        .ok_or_else(|| ApiError::new(ErrorCode::MissingSessionId, format!("Missing {SESSION_HEADER} header")))?;
+    let session = sessions
+        .read()
+        .await
+        .get(session_id)
+        .filter(|s| s.transport == Transport::StreamableHttp)
+        .cloned()
+        .ok_or_else(|| ApiError::new(ErrorCode::SessionNotFound, format!("Session '{session_id}' not found")))?;
+    session.check_caller(principal)?;
+
+    if let Some(version) = header_value(req, PROTOCOL_HEADER) {
+        if !PROTOCOL_VERSIONS.contains(&version) {
+            return Err(ApiError::new(
+                ErrorCode::UnsupportedProtocolVersion,
+                format!("Unsupported MCP protocol version '{version}'"),
+            ));
+        }
+        if let Some(negotiated) = session.protocol_version().filter(|n| n != version) {
+            return Err(ApiError::new(
+                ErrorCode::UnsupportedProtocolVersion,
+                format!("Session negotiated protocol version '{negotiated}', not '{version}'"),
+            ));
+        }
+    }
+    Ok((session_id.to_string(), session))
+}
+
+/// Whether the client accepts a plain JSON response. Clients that only
+/// accept `text/event-stream` get the response as an SSE stream instead.
+fn accepts_json(req: &HttpRequest) -> bool {
+    match header_value(req, header::ACCEPT.as_str()) {
+        Some(accept) => {
+            accept.contains("application/json") || accept.contains("*/*") || !accept.contains("text/event-stream")
+        }
+        None => true,
+    }
+}
+
+// ── POST /mcp ────────────────────────────────────────────────────
+
+pub async fn post_handler(
+    req: HttpRequest,
+    principal: Principal,
+    body: web::Bytes,
+    state: web::Data<AppState>,
+    config: web::Data<Config>,
+    sessions: web::Data<Sessions>,
+) -> Result<HttpResponse, ApiError> {
+    check_origin(&req, &config)?;
+    let message: serde_json::Value = match serde_json::from_slice(&body) {
+        Ok(m) => m,
+        Err(e) => return Ok(HttpResponse::BadRequest().content_type("application/json").body(parse_error(e))),
+    };
+
+    // `initialize` starts a new session; everything else must name one.
+    let (session_id, session) = if is_initialize(&message) {
+        let write_access = has_write_access(&req, &principal, &config);
+        (None, Arc::new(Session::new(Transport::StreamableHttp, principal, source_ip(&req), write_access)))
+    } else {
+        let (session_id, session) = find_session(&req, &principal, &sessions).await?;
+        // Every request carries its own credentials, e.g. a refreshed
+        // token; a change in access changes the session's tool list.
+        session.update_tool_context(|ctx| ctx.write_access = has_write_access(&req, &principal, &config));
+        session.set_caller(principal, source_ip(&req));
+        (Some(session_id), session)
+    };
+
+    let response = process(&session, message, &state);
+    // A new session is only kept once `initialize` has negotiated a protocol
+    // version, so failed attempts don't count against MCP_MAX_SESSIONS.
+    let session_id = match session_id {
+        Some(session_id) => Some(session_id),
+        None if session.protocol_version().is_some() => Some(register(&sessions, session.clone(), &config).await?),
+        None => None,
+    };
+    let Some(json) = response else {
+        return Ok(HttpResponse::Accepted().finish());
+    };
+
+    let mut builder = HttpResponse::Ok();
+    if let Some(session_id) = session_id {
+        builder.insert_header((SESSION_HEADER, session_id));
+    }
+    if accepts_json(&req) {
+        return Ok(builder.content_type("application/json").body(json));
+    }
+
+    // SSE upgrade: the response is the only event; it is recorded so a
+    // dropped connection can be resumed through GET with Last-Event-ID.
+    let event = session.record(json);
+    let stream = async_stream::stream! {
+        yield Ok::<_, std::convert::Infallible>(event);
+    };
+    Ok(builder
+        .content_type("text/event-stream")
+        .insert_header((header::CACHE_CONTROL, "no-cache"))
+        .body(sse::Sse::from_stream(stream)))
+}
+
+// ── GET /mcp ─────────────────────────────────────────────────────
+
+pub async fn get_handler(
+    req: HttpRequest,
+    principal: Principal,
+    config: web::Data<Config>,
+    sessions: web::Data<Sessions>,
+) -> Result<HttpResponse, ApiError> {
+    check_origin(&req, &config)?;
+    let (session_id, session) = find_session(&req, &principal, &sessions).await?;
+
+    let replay = match header_value(&req, "Last-Event-ID").and_then(|id| id.parse::<u64>().ok()) {
+        Some(last_event_id) => session.replay_after(last_event_id),
+        None => Vec::new(),
+    };
+
+    let (tx, mut rx) = mpsc::channel::<sse::Event>(STREAM_BUFFER);
+    session.attach(tx);
+    session.touch();
+
+    let stream = async_stream::stream! {
+        for event in replay {
+            yield Ok::<_, std::convert::Infallible>(event);
+        }
+        loop {
+            match tokio::time::timeout(Duration::from_secs(30), rx.recv()).await {
+                Ok(Some(event)) => yield Ok::<_, std::convert::Infallible>(event),
+                Ok(None) => break,
+                Err(_) => yield Ok::<_, std::convert::Infallible>(sse::Event::Comment("keepalive".into())),
+            }
+        }
+    };
+
+    Ok(HttpResponse::Ok()
+        .insert_header((SESSION_HEADER, session_id))
+        .content_type("text/event-stream")
+        .insert_header((header::CACHE_CONTROL, "no-cache"))
+        .body(sse::Sse::from_stream(stream).with_keep_alive(Duration::from_secs(15))))
+}
+
+// ── DELETE /mcp ──────────────────────────────────────────────────
+
+pub async fn delete_handler(
+    req: HttpRequest,
+    principal: Principal,
+    config: web::Data<Config>,
+    sessions: web::Data<Sessions>,
+) -> Result<HttpResponse, ApiError> {
+    check_origin(&req, &config)?;
+    let (session_id, session) = find_session(&req, &principal, &sessions).await?;
+    session.detach();
+    sessions.write().await.remove(&session_id);
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -0,0 +1,316 @@
+//! The MCP tool registry. Each tool is declared once with typed argument
+//! and output structs; `tools/list` is generated from the registry and
+//! `tools/call` deserializes arguments into the argument struct before
+//! running the handler, returning its output as `structuredContent`.
+//! Which tools a session sees depends on its `ToolContext`.
+
+use std::sync::LazyLock;
+
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use super::views::{self, ListArgs};
+use super::{write_tools, Session};
+use crate::storage::AppState;
+
+/// Runs a tool: `Ok` carries the structured output, `Err` the message for
+/// an `isError` result.
+type Handler = Box<dyn Fn(serde_json::Value, &AppState, &Session) -> Result<serde_json::Value, String> + Send + Sync>;
+
+/// What decides the tools a session sees.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ToolContext {
+    /// Write tools are listed and callable.
+    pub write_access: bool,
+    /// The team selected with `select_workspace`; workspace tools need one.
+    pub workspace: Option<String>,
+}
+
+pub struct Tool {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// Only listed and callable in sessions with write access.
+    pub write: bool,
+    /// Only listed and callable once the session selected a workspace.
+    pub needs_workspace: bool,
+    input_schema: serde_json::Value,
+    output_schema: serde_json::Value,
+    /// Deserializes the arguments without calling the handler.
+    check_args: fn(&str, serde_json::Value) -> Result<(), String>,
+    handler: Handler,
+}
+
+impl Tool {
+    /// A tool whose arguments deserialize into `A` and whose output is an
+    /// `O`. `handler` returns the output, or the message for an error
+    /// result. Arguments that don't match `A` are reported as an error
+    /// result too, so the agent can correct the call.
+    pub fn new<A, O>(name: &'static str, description: &'static str, handler: fn(A, &AppState) -> Result<O, String>) -> Self
+    where
+        A: DeserializeOwned + JsonSchema + 'static,
+        O: Serialize + JsonSchema + 'static,
+    {
+        Self::with_session(name, description, move |args: A, state, _| handler(args, state))
+    }
+
+    /// Like `new`, for handlers that read or change the calling session.
+    pub fn with_session<A, O, F>(name: &'static str, description: &'static str, handler: F) -> Self
+    where
+        A: DeserializeOwned + JsonSchema + 'static,
+        O: Serialize + JsonSchema + 'static,
+        F: Fn(A, &AppState, &Session) -> Result<O, String> + Send + Sync + 'static,
+    {
+        Self {
+            name,
+            description,
+            write: false,
+            needs_workspace: false,
+            input_schema: schema_for::<A>(),
+            output_schema: schema_for::<O>(),
+            check_args: |name, args| parse_args::<A>(name, args).map(drop),
+            handler: Box::new(move |args, state, session| {
+                let args = parse_args(name, args)?;
+                let output = handler(args, state, session)?;
+                Ok(serde_json::to_value(output).unwrap())
+            }),
+        }
+    }
+
+    pub fn write(mut self) -> Self {
+        self.write = true;
+        self
+    }
+
+    pub fn workspace(mut self) -> Self {
+        self.needs_workspace = true;
+        self
+    }
+
+    /// Whether a session with `ctx` may see and call this tool.
+    pub fn available(&self, ctx: &ToolContext) -> bool {
+        (!self.write || ctx.write_access) && (!self.needs_workspace || ctx.workspace.is_some())
+    }
+
+    /// The entry for this tool in `tools/list`.
+    pub fn definition(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": self.name,
+            "description": self.description,
+            "inputSchema": self.input_schema,
+            "outputSchema": self.output_schema
+        })
+    }
+
+    pub fn call(&self, args: serde_json::Value, state: &AppState, session: &Session) -> Result<serde_json::Value, String> {
+        (self.handler)(args, state, session)
+    }
+
+    /// Fails with the same message as `call` if `args` don't fit this
+    /// tool, e.g. before forwarding the call elsewhere.
+    pub fn check_args(&self, args: &serde_json::Value) -> Result<(), String> {
+        (self.check_args)(self.name, args.clone())
+    }
+}
+
+/// Deserializes the arguments of tool `name`, saying where they don't fit.
+fn parse_args<A: DeserializeOwned>(name: &str, args: serde_json::Value) -> Result<A, String> {
+    serde_path_to_error::deserialize(args).map_err(|e| {
+        let path = e.path().to_string();
+        if path == "." {
+            format!("Invalid arguments for {name}: {}", e.inner())
+        } else {
+            format!("Invalid arguments for {name}: {path}: {}", e.inner())
+        }
+    })
+}
+
+/// JSON Schema for `T` with nested types inlined, as MCP clients expect a
+/// self-contained `inputSchema` and `outputSchema`.
+pub fn schema_for<T: JsonSchema>() -> serde_json::Value {
+    let schema = schemars::generate::SchemaSettings::draft2020_12()
+        .with(|s| s.inline_subschemas = true)
+        .into_generator()
+        .into_root_schema_for::<T>();
+    let mut value = serde_json::to_value(schema).unwrap();
+    if let Some(obj) = value.as_object_mut() {
+        obj.remove("$schema");
+    }
+    value
+}
+
+static REGISTRY: LazyLock<Vec<Tool>> = LazyLock::new(|| {
+    let mut tools = read_tools();
+    tools.extend(write_tools::tools());
+    tools
+});
+
+pub fn find(name: &str) -> Option<&'static Tool> {
+    REGISTRY.iter().find(|t| t.name == name)
+}
+
+pub fn is_write_tool(name: &str) -> bool {
+    find(name).is_some_and(|t| t.write)
+}
+
+/// The tools visible to a session with `ctx`.
+pub fn visible(ctx: &ToolContext) -> impl Iterator<Item = &'static Tool> + '_ {
+    REGISTRY.iter().filter(|t| t.available(ctx))
+}
+
+/// The `tools/list` entries for a session with `ctx`.
+pub fn list(ctx: &ToolContext) -> Vec<serde_json::Value> {
+    visible(ctx).map(Tool::definition).collect()
+}
+
+// ── Read tools ───────────────────────────────────────────────────
+
+#[derive(Deserialize, JsonSchema)]
+pub struct NoArgs {}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ServiceArgs {
+    /// Service name, e.g. 'contest-engine-grpc'
+    pub name: String,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct QueueContractArgs {
+    /// Topic name, e.g. 'user-registered'
+    pub topic: String,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct NosqlContractArgs {
+    /// Entity name, e.g. 'BrokerContestSettings'
+    pub entity: String,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ProtoContractArgs {
+    /// Proto contract name, e.g. 'UsersGrpcService'
+    pub name: String,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct SelectWorkspaceArgs {
+    /// Team to work in, e.g. 'trading'. Omit to leave the current workspace.
+    pub workspace: Option<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct WorkspaceSelection {
+    pub workspace: Option<String>,
+    /// Every workspace that can be selected.
+    pub available: Vec<String>,
+}
+
+fn read_tools() -> Vec<Tool> {
+    vec![
+        Tool::new(
+            "summarize_datasource",
+            "Get entity counts, services per team and a one-line overview of every service. Start here on large registries.",
+            |_: NoArgs, state| Ok(views::overview(&state.get_datasource())),
+        ),
+        Tool::with_session(
+            "select_workspace",
+            "Select the team workspace to work in. Unlocks workspace tools such as get_workspace.",
+            |args: SelectWorkspaceArgs, state, session| {
+                let available = views::teams(&state.get_datasource());
+                if let Some(workspace) = args.workspace.as_ref().filter(|w| !available.contains(w)) {
+                    return Err(format!(
+                        "Unknown workspace '{workspace}'; available: {}",
+                        available.join(", ")
+                    ));
+                }
+                session.update_tool_context(|ctx| ctx.workspace = args.workspace.clone());
+                Ok(WorkspaceSelection {
+                    workspace: args.workspace,
+                    available,
+                })
+            },
+        ),
+        Tool::with_session(
+            "get_workspace",
+            "Get the services of the selected workspace with the contracts they serve, call, publish and consume.",
+            |_: NoArgs, state, session| {
+                let workspace = session.tool_context().workspace.ok_or("No workspace selected")?;
+                Ok(views::workspace(&state.get_datasource(), &workspace))
+            },
+        )
+        .workspace(),
+        Tool::new(
+            "get_datasource",
+            "Get the datasource including all services, queue contracts, nosql contracts, and proto contracts. Large registries are paged; pass next_cursor to continue.",
+            |args: ListArgs, state| views::datasource_page(state.get_datasource(), &args),
+        ),
+        Tool::new(
+            "list_services",
+            "List all microservices with their gRPC servers/clients, queue bindings, and metadata.",
+            |args: ListArgs, state| {
+                let items = views::entries(state.get_services(), args.detail, views::summarize_service);
+                views::paginate(items, &args)
+            },
+        ),
+        Tool::new(
+            "get_service",
+            "Get a single microservice definition by name.",
+            |args: ServiceArgs, state| {
+                state
+                    .get_service(&args.name)
+                    .ok_or_else(|| format!("Service '{}' not found", args.name))
+            },
+        ),
+        Tool::new(
+            "list_queue_contracts",
+            "List all service-bus queue/topic contracts with their message schemas.",
+            |args: ListArgs, state| {
+                let items = views::entries(state.get_queue_contracts(), args.detail, views::summarize_queue_contract);
+                views::paginate(items, &args)
+            },
+        ),
+        Tool::new(
+            "get_queue_contract",
+            "Get a single queue/topic contract by topic name, including its message schema.",
+            |args: QueueContractArgs, state| {
+                state
+                    .get_queue_contract(&args.topic)
+                    .ok_or_else(|| format!("Queue contract '{}' not found", args.topic))
+            },
+        ),
+        Tool::new(
+            "list_nosql_contracts",
+            "List all NoSQL entity contracts with their table names and schemas.",
+            |args: ListArgs, state| {
+                let items = views::entries(state.get_nosql_contracts(), args.detail, views::summarize_nosql_contract);
+                views::paginate(items, &args)
+            },
+        ),
+        Tool::new(
+            "get_nosql_contract",
+            "Get a single NoSQL entity contract by entity name, including its schema.",
+            |args: NosqlContractArgs, state| {
+                state
+                    .get_nosql_contract(&args.entity)
+                    .ok_or_else(|| format!("NoSQL contract '{}' not found", args.entity))
+            },
+        ),
+        Tool::new(
+            "list_proto_contracts",
+            "List all protobuf/gRPC contracts with their raw .proto definitions.",
+            |args: ListArgs, state| {
+                let items = views::entries(state.get_proto_contracts(), args.detail, views::summarize_proto_contract);
+                views::paginate(items, &args)
+            },
+        ),
+        Tool::new(
+            "get_proto_contract",
+            "Get a single protobuf/gRPC contract by name, including the raw .proto file text.",
+            |args: ProtoContractArgs, state| {
+                state
+                    .get_proto_contract(&args.name)
+                    .ok_or_else(|| format!("Proto contract '{}' not found", args.name))
+            },
+        ),
+    ]
+}
@@ -0,0 +1,344 @@
+//! Context-friendly views of the registry for MCP tools: one-line
+//! summaries instead of full definitions, and pages bounded by an
+//! approximate token budget.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::model::{Datasource, Entity, NosqlContract, ProtoContract, QueueContract, ServiceDefinition};
+
+/// Token budget of a page when the caller does not pass `max_tokens`.
+const DEFAULT_MAX_TOKENS: usize = 10_000;
+
+/// Rough token count of `value` serialized as compact JSON.
+fn estimate_tokens<T: Serialize>(value: &T) -> usize {
+    serde_json::to_string(value).map(|s| s.len() / 4 + 1).unwrap_or(0)
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Detail {
+    /// A few identifying fields per entity.
+    Summary,
+    /// Complete definitions.
+    #[default]
+    Full,
+}
+
+/// Verbosity and paging arguments shared by the list tools.
+#[derive(Deserialize, JsonSchema)]
+pub struct ListArgs {
+    /// 'summary' for a short entry per entity, 'full' (default) for complete definitions.
+    #[serde(default)]
+    pub detail: Detail,
+    /// Approximate token budget for this page (default 10000). At least one entity is always returned.
+    pub max_tokens: Option<usize>,
+    /// `next_cursor` from the previous page.
+    pub cursor: Option<String>,
+}
+
+/// One page of a list. `next_cursor` is present when more entities follow.
+#[derive(Serialize, JsonSchema)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Number of entities across all pages.
+    pub total: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+/// Splits `items` into the page selected by `args`.
+pub fn paginate<T: Serialize>(items: Vec<T>, args: &ListArgs) -> Result<Page<T>, String> {
+    let total = items.len();
+    let start = match &args.cursor {
+        Some(cursor) => cursor
+            .parse::<usize>()
+            .ok()
+            .filter(|offset| *offset <= total)
+            .ok_or_else(|| format!("Invalid cursor '{cursor}'"))?,
+        None => 0,
+    };
+    let budget = args.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS);
+
+    let mut used = 0;
+    let mut page = Vec::new();
+    for item in items.into_iter().skip(start) {
+        let cost = estimate_tokens(&item);
+        if !page.is_empty() && used + cost > budget {
+            break;
+        }
+        used += cost;
+        page.push(item);
+    }
+    let end = start + page.len();
+    Ok(Page {
+        items: page,
+        total,
+        next_cursor: (end < total).then(|| end.to_string()),
+    })
+}
+
+/// An entity at the requested level of detail.
+#[derive(Serialize, JsonSchema)]
+#[serde(untagged)]
+pub enum Entry<F, S> {
+    Full(F),
+    Summary(S),
+}
+
+pub fn entries<F, S>(items: Vec<F>, detail: Detail, summarize: fn(&F) -> S) -> Vec<Entry<F, S>> {
+    match detail {
+        Detail::Full => items.into_iter().map(Entry::Full).collect(),
+        Detail::Summary => items.iter().map(|i| Entry::Summary(summarize(i))).collect(),
+    }
+}
+
+// ── Summaries ────────────────────────────────────────────────────
+
+/// The owning teams of `entity`, for a summary.
+fn owners<T: Entity>(entity: &T) -> Vec<String> {
+    entity.owning_teams().into_iter().map(str::to_string).collect()
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ServiceSummary {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub service_type: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub owners: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+pub fn summarize_service(svc: &ServiceDefinition) -> ServiceSummary {
+    ServiceSummary {
+        name: svc.name.clone(),
+        service_type: svc.service_type.clone(),
+        owners: owners(svc),
+        description: svc.description.clone(),
+    }
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct QueueContractSummary {
+    pub topic_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Name of the message schema, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub owners: Vec<String>,
+}
+
+pub fn summarize_queue_contract(qc: &QueueContract) -> QueueContractSummary {
+    QueueContractSummary {
+        topic_name: qc.topic_name.clone(),
+        description: qc.description.clone(),
+        message: qc.message_schema.as_ref().map(|m| m.name.clone()),
+        owners: owners(qc),
+    }
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct NosqlContractSummary {
+    pub entity_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub table_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub owners: Vec<String>,
+}
+
+pub fn summarize_nosql_contract(nc: &NosqlContract) -> NosqlContractSummary {
+    NosqlContractSummary {
+        entity_name: nc.entity_name.clone(),
+        table_name: nc.table_name.clone(),
+        description: nc.description.clone(),
+        owners: owners(nc),
+    }
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ProtoContractSummary {
+    pub name: String,
+    /// `service` declarations in the .proto.
+    pub services: Vec<String>,
+    /// `rpc` method names in the .proto.
+    pub rpcs: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub owners: Vec<String>,
+}
+
+pub fn summarize_proto_contract(pc: &ProtoContract) -> ProtoContractSummary {
+    let declared = |keyword: &str| -> Vec<String> {
+        pc.raw_proto
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix(keyword))
+            .filter_map(|rest| rest.split(|c: char| !c.is_alphanumeric() && c != '_').find(|s| !s.is_empty()))
+            .map(str::to_string)
+            .collect()
+    };
+    ProtoContractSummary {
+        name: pc.name.clone(),
+        services: declared("service "),
+        rpcs: declared("rpc "),
+        owners: owners(pc),
+    }
+}
+
+// ── Whole datasource ─────────────────────────────────────────────
+
+/// One page of the datasource. Entities are paged in the order services,
+/// queue contracts, NoSQL contracts, proto contracts.
+#[derive(Serialize, JsonSchema)]
+pub struct DatasourcePage {
+    pub services: Vec<Entry<ServiceDefinition, ServiceSummary>>,
+    pub queue_contracts: Vec<Entry<QueueContract, QueueContractSummary>>,
+    pub nosql_contracts: Vec<Entry<NosqlContract, NosqlContractSummary>>,
+    pub proto_contracts: Vec<Entry<ProtoContract, ProtoContractSummary>>,
+    /// Number of entities across all pages.
+    pub total: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum AnyEntry {
+    Service(Entry<ServiceDefinition, ServiceSummary>),
+    QueueContract(Entry<QueueContract, QueueContractSummary>),
+    NosqlContract(Entry<NosqlContract, NosqlContractSummary>),
+    ProtoContract(Entry<ProtoContract, ProtoContractSummary>),
+}
+
+pub fn datasource_page(ds: Datasource, args: &ListArgs) -> Result<DatasourcePage, String> {
+    let mut all = Vec::new();
+    all.extend(entries(ds.services, args.detail, summarize_service).into_iter().map(AnyEntry::Service));
+    all.extend(
+        entries(ds.queue_contracts, args.detail, summarize_queue_contract)
+            .into_iter()
+            .map(AnyEntry::QueueContract),
+    );
+    all.extend(
+        entries(ds.nosql_contracts, args.detail, summarize_nosql_contract)
+            .into_iter()
+            .map(AnyEntry::NosqlContract),
+    );
+    all.extend(
+        entries(ds.proto_contracts, args.detail, summarize_proto_contract)
+            .into_iter()
+            .map(AnyEntry::ProtoContract),
+    );
+
+    let page = paginate(all, args)?;
+    let mut out = DatasourcePage {
+        services: Vec::new(),
+        queue_contracts: Vec::new(),
+        nosql_contracts: Vec::new(),
+        proto_contracts: Vec::new(),
+        total: page.total,
+        next_cursor: page.next_cursor,
+    };
+    for item in page.items {
+        match item {
+            AnyEntry::Service(e) => out.services.push(e),
+            AnyEntry::QueueContract(e) => out.queue_contracts.push(e),
+            AnyEntry::NosqlContract(e) => out.nosql_contracts.push(e),
+            AnyEntry::ProtoContract(e) => out.proto_contracts.push(e),
+        }
+    }
+    Ok(out)
+}
+
+/// Every team that owns at least one entity.
+pub fn teams(ds: &Datasource) -> Vec<String> {
+    let mut teams = BTreeSet::new();
+    teams.extend(ds.services.iter().flat_map(Entity::owning_teams));
+    teams.extend(ds.queue_contracts.iter().flat_map(Entity::owning_teams));
+    teams.extend(ds.nosql_contracts.iter().flat_map(Entity::owning_teams));
+    teams.extend(ds.proto_contracts.iter().flat_map(Entity::owning_teams));
+    teams.into_iter().map(str::to_string).collect()
+}
+
+/// Output of `get_workspace`: a team's services and the contracts they touch.
+#[derive(Serialize, JsonSchema)]
+pub struct WorkspaceView {
+    pub workspace: String,
+    pub services: Vec<ServiceSummary>,
+    /// Proto contracts the services serve or call.
+    pub proto_contracts: Vec<String>,
+    /// Topics the services publish or subscribe to.
+    pub queue_contracts: Vec<String>,
+}
+
+pub fn workspace(ds: &Datasource, name: &str) -> WorkspaceView {
+    let services: Vec<&ServiceDefinition> =
+        ds.services.iter().filter(|s| s.owning_teams().contains(&name)).collect();
+    let mut protos = BTreeSet::new();
+    let mut topics = BTreeSet::new();
+    for svc in &services {
+        protos.extend(svc.grpc_servers.iter().chain(&svc.grpc_clients).flatten().cloned());
+        if let Some(queue) = &svc.queue {
+            topics.extend(queue.publish_queues.iter().chain(&queue.subscribe_queues).flatten().cloned());
+        }
+    }
+    WorkspaceView {
+        workspace: name.to_string(),
+        services: services.into_iter().map(summarize_service).collect(),
+        proto_contracts: protos.into_iter().collect(),
+        queue_contracts: topics.into_iter().collect(),
+    }
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct EntityCounts {
+    pub services: usize,
+    pub queue_contracts: usize,
+    pub nosql_contracts: usize,
+    pub proto_contracts: usize,
+}
+
+/// Output of `summarize_datasource`.
+#[derive(Serialize, JsonSchema)]
+pub struct DatasourceOverview {
+    pub counts: EntityCounts,
+    /// Number of services per owning team; unowned services count as 'unassigned'.
+    pub teams: BTreeMap<String, usize>,
+    /// One line per service: `name (type, owners): description`.
+    pub services: Vec<String>,
+}
+
+pub fn overview(ds: &Datasource) -> DatasourceOverview {
+    let mut teams = BTreeMap::new();
+    let mut lines = Vec::new();
+    for svc in &ds.services {
+        let mut owners = svc.owning_teams();
+        if owners.is_empty() {
+            owners.push("unassigned");
+        }
+        for team in &owners {
+            *teams.entry(team.to_string()).or_insert(0) += 1;
+        }
+        let mut line = format!("{} ({}, {})", svc.name, svc.service_type, owners.join(", "));
+        if let Some(description) = &svc.description {
+            line.push_str(": ");
+            line.push_str(description.lines().next().unwrap_or_default());
+        }
+        lines.push(line);
+    }
+    DatasourceOverview {
+        counts: EntityCounts {
+            services: ds.services.len(),
+            queue_contracts: ds.queue_contracts.len(),
+            nosql_contracts: ds.nosql_contracts.len(),
+            proto_contracts: ds.proto_contracts.len(),
+        },
+        teams,
+        services: lines,
+    }
+}
@@ -0,0 +1,193 @@
+//! MCP tools that modify the registry. They are only listed and callable
+//! for sessions with write access (see `ToolContext::write_access`), and
+//! each call is checked against the owners of the entity it changes.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::tools::Tool;
+use crate::model::{EntityKind, NosqlContract, ProtoContract, QueueContract, ServiceDefinition};
+use crate::storage::StorageError;
+
+#[derive(Deserialize, JsonSchema)]
+pub struct DeleteServiceArgs {
+    /// Service name, e.g. 'contest-engine-grpc'.
+    pub name: String,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct DeleteQueueContractArgs {
+    /// Topic name, e.g. 'user-registered'.
+    pub topic: String,
+    /// Also remove the topic from services' subscribe_queues.
+    #[serde(default)]
+    pub cascade: bool,
+    /// Delete even if services still subscribe to the topic.
+    #[serde(default)]
+    pub force: bool,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct DeleteNosqlContractArgs {
+    /// Entity name, e.g. 'BrokerContestSettings'.
+    pub entity: String,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct DeleteProtoContractArgs {
+    /// Proto contract name, e.g. 'UsersGrpcService'.
+    pub name: String,
+    /// Also remove the contract from services' grpc_clients.
+    #[serde(default)]
+    pub cascade: bool,
+    /// Delete even if services still use the contract as a gRPC client.
+    #[serde(default)]
+    pub force: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum WriteStatus {
+    Saved,
+    Deleted,
+}
+
+/// Output of every write tool.
+#[derive(Serialize, JsonSchema)]
+pub struct WriteResult {
+    pub kind: EntityKind,
+    pub key: String,
+    pub status: WriteStatus,
+    /// Services whose references to a deleted contract were removed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub updated_services: Vec<String>,
+}
+
+impl WriteResult {
+    fn saved(kind: EntityKind, key: String) -> Self {
+        Self {
+            kind,
+            key,
+            status: WriteStatus::Saved,
+            updated_services: Vec::new(),
+        }
+    }
+
+    fn deleted(kind: EntityKind, key: String, updated_services: Vec<String>) -> Self {
+        Self {
+            kind,
+            key,
+            status: WriteStatus::Deleted,
+            updated_services,
+        }
+    }
+}
+
+/// The result of write tool `tool` when it was performed elsewhere, as the
+/// stdio binary does by forwarding to the REST API.
+pub fn forwarded_result(tool: &str, args: &serde_json::Value, updated_services: Vec<String>) -> Option<WriteResult> {
+    let (kind, status, key_field) = match tool {
+        "upsert_service" => (EntityKind::Service, WriteStatus::Saved, "name"),
+        "delete_service" => (EntityKind::Service, WriteStatus::Deleted, "name"),
+        "upsert_queue_contract" => (EntityKind::QueueContract, WriteStatus::Saved, "topic_name"),
+        "delete_queue_contract" => (EntityKind::QueueContract, WriteStatus::Deleted, "topic"),
+        "upsert_nosql_contract" => (EntityKind::NosqlContract, WriteStatus::Saved, "entity_name"),
+        "delete_nosql_contract" => (EntityKind::NosqlContract, WriteStatus::Deleted, "entity"),
+        "upsert_proto_contract" => (EntityKind::ProtoContract, WriteStatus::Saved, "name"),
+        "delete_proto_contract" => (EntityKind::ProtoContract, WriteStatus::Deleted, "name"),
+        _ => return None,
+    };
+    Some(WriteResult {
+        kind,
+        key: args.get(key_field)?.as_str()?.to_string(),
+        status,
+        updated_services,
+    })
+}
+
+fn storage_err(e: StorageError) -> String {
+    e.to_string()
+}
+
+pub fn tools() -> Vec<Tool> {
+    vec![
+        Tool::with_session(
+            "upsert_service",
+            "Register a microservice or replace its definition. Pass the full service definition.",
+            |svc: ServiceDefinition, state, session| {
+                let key = svc.name.clone();
+                state.upsert_service(svc, &session.actor()).map_err(storage_err)?;
+                Ok(WriteResult::saved(EntityKind::Service, key))
+            },
+        )
+        .write(),
+        Tool::with_session(
+            "delete_service",
+            "Delete a microservice definition by name.",
+            |args: DeleteServiceArgs, state, session| {
+                state.delete_service(&args.name, &session.actor()).map_err(storage_err)?;
+                Ok(WriteResult::deleted(EntityKind::Service, args.name, Vec::new()))
+            },
+        )
+        .write(),
+        Tool::with_session(
+            "upsert_queue_contract",
+            "Create or replace a service-bus queue/topic contract, including its message schema.",
+            |qc: QueueContract, state, session| {
+                let key = qc.topic_name.clone();
+                state.upsert_queue_contract(qc, &session.actor()).map_err(storage_err)?;
+                Ok(WriteResult::saved(EntityKind::QueueContract, key))
+            },
+        )
+        .write(),
+        Tool::with_session(
+            "delete_queue_contract",
+            "Delete a queue/topic contract. Fails while services subscribe to it unless cascade or force is set.",
+            |args: DeleteQueueContractArgs, state, session| {
+                let mode = state.delete_mode(args.cascade, args.force);
+                let updated = state.delete_queue_contract(&args.topic, mode, &session.actor()).map_err(storage_err)?;
+                Ok(WriteResult::deleted(EntityKind::QueueContract, args.topic, updated))
+            },
+        )
+        .write(),
+        Tool::with_session(
+            "upsert_nosql_contract",
+            "Create or replace a NoSQL entity contract, including its schema.",
+            |nc: NosqlContract, state, session| {
+                let key = nc.entity_name.clone();
+                state.upsert_nosql_contract(nc, &session.actor()).map_err(storage_err)?;
+                Ok(WriteResult::saved(EntityKind::NosqlContract, key))
+            },
+        )
+        .write(),
+        Tool::with_session(
+            "delete_nosql_contract",
+            "Delete a NoSQL entity contract by entity name.",
+            |args: DeleteNosqlContractArgs, state, session| {
+                state.delete_nosql_contract(&args.entity, &session.actor()).map_err(storage_err)?;
+                Ok(WriteResult::deleted(EntityKind::NosqlContract, args.entity, Vec::new()))
+            },
+        )
+        .write(),
+        Tool::with_session(
+            "upsert_proto_contract",
+            "Create or replace a protobuf/gRPC contract with its raw .proto text.",
+            |pc: ProtoContract, state, session| {
+                let key = pc.name.clone();
+                state.upsert_proto_contract(pc, &session.actor()).map_err(storage_err)?;
+                Ok(WriteResult::saved(EntityKind::ProtoContract, key))
+            },
+        )
+        .write(),
+        Tool::with_session(
+            "delete_proto_contract",
+            "Delete a protobuf/gRPC contract. Fails while services use it as a gRPC client unless cascade or force is set.",
+            |args: DeleteProtoContractArgs, state, session| {
+                let mode = state.delete_mode(args.cascade, args.force);
+                let updated = state.delete_proto_contract(&args.name, mode, &session.actor()).map_err(storage_err)?;
+                Ok(WriteResult::deleted(EntityKind::ProtoContract, args.name, updated))
+            },
+        )
+        .write(),
+    ]
+}
@@ -1,6 +1,134 @@
+pub mod admin;
+pub mod audit;
+pub mod batch;
 pub mod datasource;
+pub mod health;
+pub mod events;
 pub mod mcp;
 pub mod nosql;
+pub mod oauth;
 pub mod proto;
 pub mod queue;
 pub mod services;
+pub mod webhooks;
+
+use std::future::Future;
+use std::pin::Pin;
+
+use actix_web::dev::Payload;
+use actix_web::{http::header, web, FromRequest, HttpRequest, HttpResponse};
+
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use crate::config::Config;
+use crate::error::{json_pointer, ApiError, ErrorCode, FieldError};
+use crate::storage::{AppState, DeleteMode, PatchDocument, StorageError};
+
+/// A JSON request body, like `web::Json`, except that a body of the wrong
+/// shape is rejected with the JSON Pointer of the field at fault.
+pub struct Json<T>(pub T);
+
+impl<T> Json<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> std::ops::Deref for Json<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: DeserializeOwned + 'static> FromRequest for Json<T> {
+    type Error = actix_web::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        // Content type, size limit and syntax are checked as by `web::Json`.
+        let value = web::Json::<serde_json::Value>::from_request(req, payload);
+        Box::pin(async move {
+            let value = value.await?.into_inner();
+            serde_path_to_error::deserialize(value).map(Json).map_err(|e| {
+                let path = json_pointer(e.path());
+                let message = e.into_inner().to_string();
+                let detail = match path.as_str() {
+                    "" => format!("Invalid request body: {message}"),
+                    path => format!("Field '{path}' is invalid: {message}"),
+                };
+                ApiError::new(ErrorCode::InvalidBody, detail)
+                    .with_errors(vec![FieldError::new(path, message)])
+                    .into()
+            })
+        })
+    }
+}
+
+/// The absolute URL clients use to reach `path` on this server:
+/// `PUBLIC_BASE_URL` if set, otherwise the scheme and host the client
+/// used, as reported by a proxy's `Forwarded` or `X-Forwarded-Proto`/
+/// `X-Forwarded-Host` headers or else the `Host` header, followed by
+/// `PATH_PREFIX`.
+pub fn public_url(req: &HttpRequest, config: &Config, path: &str) -> String {
+    let origin = match &config.public_base_url {
+        Some(base) => base.clone(),
+        None => {
+            let info = req.connection_info();
+            format!("{}://{}", info.scheme(), info.host())
+        }
+    };
+    format!("{origin}{}{path}", config.path_prefix)
+}
+
+/// Parses a PATCH body according to its `Content-Type`:
+/// `application/json-patch+json` is an RFC 6902 JSON Patch, while
+/// `application/merge-patch+json` (or plain `application/json`) is an
+/// RFC 7396 JSON Merge Patch.
+pub fn parse_patch(req: &HttpRequest, body: &web::Bytes) -> Result<PatchDocument, ApiError> {
+    let content_type = req
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|h| h.to_str().ok())
+        .unwrap_or("application/merge-patch+json");
+    let mime = content_type.split(';').next().unwrap_or("").trim();
+
+    let bad_request = |e: serde_json::Error| ApiError::new(ErrorCode::InvalidBody, format!("Invalid patch document: {e}"));
+
+    match mime {
+        "application/json-patch+json" => serde_json::from_slice(body).map(PatchDocument::Json).map_err(bad_request),
+        "application/merge-patch+json" | "application/json" => {
+            serde_json::from_slice(body).map(PatchDocument::Merge).map_err(bad_request)
+        }
+        _ => Err(ApiError::new(
+            ErrorCode::UnsupportedMediaType,
+            format!("Unsupported patch content type '{mime}'"),
+        )),
+    }
+}
+
+/// Query string accepted by deletes of referenceable contracts.
+#[derive(Deserialize)]
+pub struct DeleteQuery {
+    #[serde(default)]
+    cascade: bool,
+    #[serde(default)]
+    force: bool,
+}
+
+impl DeleteQuery {
+    pub fn mode(&self, state: &AppState) -> DeleteMode {
+        state.delete_mode(self.cascade, self.force)
+    }
+}
+
+pub fn delete_response(result: Result<Vec<String>, StorageError>) -> Result<HttpResponse, ApiError> {
+    let updated = result?;
+    if updated.is_empty() {
+        Ok(HttpResponse::Ok().json(serde_json::json!({"status": "deleted"})))
+    } else {
+        Ok(HttpResponse::Ok().json(serde_json::json!({"status": "deleted", "updated_services": updated})))
+    }
+}
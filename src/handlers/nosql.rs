@@ -1,5 +1,7 @@
-use actix_web::{web, HttpResponse};
+use actix_web::{web, HttpRequest, HttpResponse};
 
+use crate::audit::Actor;
+use crate::error::ApiError;
 use crate::model::NosqlContract;
 use crate::storage::AppState;
 
@@ -9,23 +11,46 @@ pub async fn list(state: web::Data<AppState>) -> HttpResponse {
 }
 
 /// GET /api/nosql-contracts/{entity}
-pub async fn get(state: web::Data<AppState>, path: web::Path<String>) -> HttpResponse {
-    match state.get_nosql_contract(&path.into_inner()) {
-        Some(n) => HttpResponse::Ok().json(n),
-        None => HttpResponse::NotFound().json(serde_json::json!({"error": "NoSQL contract not found"})),
+pub async fn get(state: web::Data<AppState>, path: web::Path<String>) -> Result<HttpResponse, ApiError> {
+    let key = path.into_inner();
+    match state.get_nosql_contract(&key) {
+        Some(n) => Ok(HttpResponse::Ok().json(n)),
+        None => Err(ApiError::not_found(format!("NoSQL contract '{key}' not found"))),
     }
 }
 
 /// POST /api/nosql-contracts — insert or replace
-pub async fn upsert(state: web::Data<AppState>, body: web::Json<NosqlContract>) -> HttpResponse {
-    state.upsert_nosql_contract(body.into_inner());
-    HttpResponse::Ok().json(serde_json::json!({"status": "ok"}))
+pub async fn upsert(
+    state: web::Data<AppState>,
+    actor: Actor,
+    body: super::Json<NosqlContract>,
+) -> Result<HttpResponse, ApiError> {
+    let contract = body.into_inner();
+    state.upsert_nosql_contract(contract, &actor)?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"status": "ok"})))
 }
 
 /// DELETE /api/nosql-contracts/{entity}
-pub async fn delete(state: web::Data<AppState>, path: web::Path<String>) -> HttpResponse {
-    match state.delete_nosql_contract(&path.into_inner()) {
-        Ok(()) => HttpResponse::Ok().json(serde_json::json!({"status": "deleted"})),
-        Err(e) => HttpResponse::NotFound().json(serde_json::json!({"error": e})),
-    }
+pub async fn delete(
+    state: web::Data<AppState>,
+    actor: Actor,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let key = path.into_inner();
+    state.delete_nosql_contract(&key, &actor)?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"status": "deleted"})))
+}
+
+/// PATCH /api/nosql-contracts/{entity} — JSON Merge Patch or JSON Patch
+pub async fn patch(
+    state: web::Data<AppState>,
+    actor: Actor,
+    path: web::Path<String>,
+    req: HttpRequest,
+    body: web::Bytes,
+) -> Result<HttpResponse, ApiError> {
+    let key = path.into_inner();
+    let patch = super::parse_patch(&req, &body)?;
+    let n = state.patch_nosql_contract(&key, &patch, &actor)?;
+    Ok(HttpResponse::Ok().json(n))
 }
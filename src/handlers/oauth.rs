@@ -0,0 +1,27 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+
+use crate::config::Config;
+use crate::error::{ApiError, ErrorCode};
+use crate::handlers::public_url;
+
+/// GET /.well-known/oauth-protected-resource — RFC 9728 metadata telling
+/// MCP clients which authorization servers issue tokens for this server.
+/// The `resource` is the JWT audience when that is a URL, so clients request
+/// tokens the server accepts; otherwise it is the MCP endpoint.
+pub async fn protected_resource(req: HttpRequest, config: web::Data<Config>) -> Result<HttpResponse, ApiError> {
+    let Some(jwt) = &config.jwt else {
+        return Err(ApiError::new(ErrorCode::RouteNotFound, "JWT authentication is not configured"));
+    };
+    let resource = if jwt.audience.starts_with("https://") || jwt.audience.starts_with("http://") {
+        jwt.audience.clone()
+    } else {
+        public_url(&req, &config, "/mcp")
+    };
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "resource": resource,
+        "authorization_servers": jwt.authorization_servers,
+        "scopes_supported": ["read", "write", "admin"],
+        "bearer_methods_supported": ["header"],
+        "resource_name": "codegang-datasource"
+    })))
+}
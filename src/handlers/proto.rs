@@ -1,5 +1,7 @@
-use actix_web::{web, HttpResponse};
+use actix_web::{web, HttpRequest, HttpResponse};
 
+use crate::audit::Actor;
+use crate::error::ApiError;
 use crate::model::ProtoContract;
 use crate::storage::AppState;
 
@@ -9,23 +11,47 @@ pub async fn list(state: web::Data<AppState>) -> HttpResponse {
 }
 
 /// GET /api/proto-contracts/{name}
-pub async fn get(state: web::Data<AppState>, path: web::Path<String>) -> HttpResponse {
-    match state.get_proto_contract(&path.into_inner()) {
-        Some(p) => HttpResponse::Ok().json(p),
-        None => HttpResponse::NotFound().json(serde_json::json!({"error": "Proto contract not found"})),
+pub async fn get(state: web::Data<AppState>, path: web::Path<String>) -> Result<HttpResponse, ApiError> {
+    let key = path.into_inner();
+    match state.get_proto_contract(&key) {
+        Some(p) => Ok(HttpResponse::Ok().json(p)),
+        None => Err(ApiError::not_found(format!("Proto contract '{key}' not found"))),
     }
 }
 
 /// POST /api/proto-contracts — insert or replace
-pub async fn upsert(state: web::Data<AppState>, body: web::Json<ProtoContract>) -> HttpResponse {
-    state.upsert_proto_contract(body.into_inner());
-    HttpResponse::Ok().json(serde_json::json!({"status": "ok"}))
+pub async fn upsert(
+    state: web::Data<AppState>,
+    actor: Actor,
+    body: super::Json<ProtoContract>,
+) -> Result<HttpResponse, ApiError> {
+    let contract = body.into_inner();
+    state.upsert_proto_contract(contract, &actor)?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"status": "ok"})))
 }
 
-/// DELETE /api/proto-contracts/{name}
-pub async fn delete(state: web::Data<AppState>, path: web::Path<String>) -> HttpResponse {
-    match state.delete_proto_contract(&path.into_inner()) {
-        Ok(()) => HttpResponse::Ok().json(serde_json::json!({"status": "deleted"})),
-        Err(e) => HttpResponse::NotFound().json(serde_json::json!({"error": e})),
-    }
+/// DELETE /api/proto-contracts/{name}?cascade=true|force=true
+pub async fn delete(
+    state: web::Data<AppState>,
+    actor: Actor,
+    path: web::Path<String>,
+    query: web::Query<super::DeleteQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let key = path.into_inner();
+    let mode = query.mode(&state);
+    super::delete_response(state.delete_proto_contract(&key, mode, &actor))
+}
+
+/// PATCH /api/proto-contracts/{name} — JSON Merge Patch or JSON Patch
+pub async fn patch(
+    state: web::Data<AppState>,
+    actor: Actor,
+    path: web::Path<String>,
+    req: HttpRequest,
+    body: web::Bytes,
+) -> Result<HttpResponse, ApiError> {
+    let key = path.into_inner();
+    let patch = super::parse_patch(&req, &body)?;
+    let p = state.patch_proto_contract(&key, &patch, &actor)?;
+    Ok(HttpResponse::Ok().json(p))
 }
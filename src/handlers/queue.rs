@@ -1,5 +1,7 @@
-use actix_web::{web, HttpResponse};
+use actix_web::{web, HttpRequest, HttpResponse};
 
+use crate::audit::Actor;
+use crate::error::ApiError;
 use crate::model::QueueContract;
 use crate::storage::AppState;
 
@@ -9,23 +11,47 @@ pub async fn list(state: web::Data<AppState>) -> HttpResponse {
 }
 
 /// GET /api/queue-contracts/{topic}
-pub async fn get(state: web::Data<AppState>, path: web::Path<String>) -> HttpResponse {
-    match state.get_queue_contract(&path.into_inner()) {
-        Some(q) => HttpResponse::Ok().json(q),
-        None => HttpResponse::NotFound().json(serde_json::json!({"error": "Queue contract not found"})),
+pub async fn get(state: web::Data<AppState>, path: web::Path<String>) -> Result<HttpResponse, ApiError> {
+    let key = path.into_inner();
+    match state.get_queue_contract(&key) {
+        Some(q) => Ok(HttpResponse::Ok().json(q)),
+        None => Err(ApiError::not_found(format!("Queue contract '{key}' not found"))),
     }
 }
 
 /// POST /api/queue-contracts — insert or replace
-pub async fn upsert(state: web::Data<AppState>, body: web::Json<QueueContract>) -> HttpResponse {
-    state.upsert_queue_contract(body.into_inner());
-    HttpResponse::Ok().json(serde_json::json!({"status": "ok"}))
+pub async fn upsert(
+    state: web::Data<AppState>,
+    actor: Actor,
+    body: super::Json<QueueContract>,
+) -> Result<HttpResponse, ApiError> {
+    let contract = body.into_inner();
+    state.upsert_queue_contract(contract, &actor)?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"status": "ok"})))
 }
 
-/// DELETE /api/queue-contracts/{topic}
-pub async fn delete(state: web::Data<AppState>, path: web::Path<String>) -> HttpResponse {
-    match state.delete_queue_contract(&path.into_inner()) {
-        Ok(()) => HttpResponse::Ok().json(serde_json::json!({"status": "deleted"})),
-        Err(e) => HttpResponse::NotFound().json(serde_json::json!({"error": e})),
-    }
+/// DELETE /api/queue-contracts/{topic}?cascade=true|force=true
+pub async fn delete(
+    state: web::Data<AppState>,
+    actor: Actor,
+    path: web::Path<String>,
+    query: web::Query<super::DeleteQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let key = path.into_inner();
+    let mode = query.mode(&state);
+    super::delete_response(state.delete_queue_contract(&key, mode, &actor))
+}
+
+/// PATCH /api/queue-contracts/{topic} — JSON Merge Patch or JSON Patch
+pub async fn patch(
+    state: web::Data<AppState>,
+    actor: Actor,
+    path: web::Path<String>,
+    req: HttpRequest,
+    body: web::Bytes,
+) -> Result<HttpResponse, ApiError> {
+    let key = path.into_inner();
+    let patch = super::parse_patch(&req, &body)?;
+    let q = state.patch_queue_contract(&key, &patch, &actor)?;
+    Ok(HttpResponse::Ok().json(q))
 }
@@ -1,5 +1,7 @@
-use actix_web::{web, HttpResponse};
+use actix_web::{web, HttpRequest, HttpResponse};
 
+use crate::audit::Actor;
+use crate::error::ApiError;
 use crate::model::ServiceDefinition;
 use crate::storage::AppState;
 
@@ -9,23 +11,46 @@ pub async fn list(state: web::Data<AppState>) -> HttpResponse {
 }
 
 /// GET /api/services/{name}
-pub async fn get(state: web::Data<AppState>, path: web::Path<String>) -> HttpResponse {
-    match state.get_service(&path.into_inner()) {
-        Some(s) => HttpResponse::Ok().json(s),
-        None => HttpResponse::NotFound().json(serde_json::json!({"error": "Service not found"})),
+pub async fn get(state: web::Data<AppState>, path: web::Path<String>) -> Result<HttpResponse, ApiError> {
+    let key = path.into_inner();
+    match state.get_service(&key) {
+        Some(s) => Ok(HttpResponse::Ok().json(s)),
+        None => Err(ApiError::not_found(format!("Service '{key}' not found"))),
     }
 }
 
 /// POST /api/services — insert or replace
-pub async fn upsert(state: web::Data<AppState>, body: web::Json<ServiceDefinition>) -> HttpResponse {
-    state.upsert_service(body.into_inner());
-    HttpResponse::Ok().json(serde_json::json!({"status": "ok"}))
+pub async fn upsert(
+    state: web::Data<AppState>,
+    actor: Actor,
+    body: super::Json<ServiceDefinition>,
+) -> Result<HttpResponse, ApiError> {
+    let svc = body.into_inner();
+    state.upsert_service(svc, &actor)?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"status": "ok"})))
 }
 
 /// DELETE /api/services/{name}
-pub async fn delete(state: web::Data<AppState>, path: web::Path<String>) -> HttpResponse {
-    match state.delete_service(&path.into_inner()) {
-        Ok(()) => HttpResponse::Ok().json(serde_json::json!({"status": "deleted"})),
-        Err(e) => HttpResponse::NotFound().json(serde_json::json!({"error": e})),
-    }
+pub async fn delete(
+    state: web::Data<AppState>,
+    actor: Actor,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let name = path.into_inner();
+    state.delete_service(&name, &actor)?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"status": "deleted"})))
+}
+
+/// PATCH /api/services/{name} — JSON Merge Patch or JSON Patch
+pub async fn patch(
+    state: web::Data<AppState>,
+    actor: Actor,
+    path: web::Path<String>,
+    req: HttpRequest,
+    body: web::Bytes,
+) -> Result<HttpResponse, ApiError> {
+    let name = path.into_inner();
+    let patch = super::parse_patch(&req, &body)?;
+    let s = state.patch_service(&name, &patch, &actor)?;
+    Ok(HttpResponse::Ok().json(s))
 }
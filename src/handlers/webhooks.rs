@@ -0,0 +1,102 @@
+use actix_web::{web, HttpResponse};
+
+use crate::auth::Principal;
+use crate::error::{ApiError, ErrorCode};
+use crate::storage::StorageError;
+use crate::webhooks::{Webhook, Webhooks};
+
+fn validate(hook: &Webhook) -> Result<(), ApiError> {
+    let errors = hook.validate();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(StorageError::Validation(errors).into())
+    }
+}
+
+fn not_found(id: &str) -> ApiError {
+    ApiError::new(ErrorCode::NotFound, format!("Webhook '{id}' not found"))
+}
+
+/// GET /api/webhooks
+pub async fn list(webhooks: web::Data<Webhooks>, principal: Principal) -> Result<HttpResponse, ApiError> {
+    principal.require_admin()?;
+    Ok(HttpResponse::Ok().json(webhooks.list()))
+}
+
+/// POST /api/webhooks — the response carries the secret, once
+pub async fn create(
+    webhooks: web::Data<Webhooks>,
+    principal: Principal,
+    body: super::Json<Webhook>,
+) -> Result<HttpResponse, ApiError> {
+    principal.require_admin()?;
+    let hook = body.into_inner();
+    validate(&hook)?;
+    Ok(HttpResponse::Created().json(webhooks.create(hook)))
+}
+
+/// GET /api/webhooks/{id}
+pub async fn get(
+    webhooks: web::Data<Webhooks>,
+    principal: Principal,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    principal.require_admin()?;
+    let id = path.into_inner();
+    webhooks.get(&id).map(|h| HttpResponse::Ok().json(h)).ok_or_else(|| not_found(&id))
+}
+
+/// PUT /api/webhooks/{id} — replace; the secret is kept unless given
+pub async fn replace(
+    webhooks: web::Data<Webhooks>,
+    principal: Principal,
+    path: web::Path<String>,
+    body: super::Json<Webhook>,
+) -> Result<HttpResponse, ApiError> {
+    principal.require_admin()?;
+    let id = path.into_inner();
+    let hook = body.into_inner();
+    validate(&hook)?;
+    webhooks.replace(&id, hook).map(|h| HttpResponse::Ok().json(h)).ok_or_else(|| not_found(&id))
+}
+
+/// DELETE /api/webhooks/{id}
+pub async fn delete(
+    webhooks: web::Data<Webhooks>,
+    principal: Principal,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    principal.require_admin()?;
+    let id = path.into_inner();
+    if !webhooks.delete(&id) {
+        return Err(not_found(&id));
+    }
+    Ok(HttpResponse::Ok().json(serde_json::json!({"status": "deleted"})))
+}
+
+/// GET /api/webhooks/{id}/deliveries — recent deliveries, newest first
+pub async fn deliveries(
+    webhooks: web::Data<Webhooks>,
+    principal: Principal,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    principal.require_admin()?;
+    let id = path.into_inner();
+    if webhooks.get(&id).is_none() {
+        return Err(not_found(&id));
+    }
+    Ok(HttpResponse::Ok().json(webhooks.deliveries(&id)))
+}
+
+/// POST /api/webhooks/{id}/test — send a `ping` once and report the result
+pub async fn test(
+    webhooks: web::Data<Webhooks>,
+    principal: Principal,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    principal.require_admin()?;
+    let id = path.into_inner();
+    let delivery = webhooks.test(&id).await.ok_or_else(|| not_found(&id))?;
+    Ok(HttpResponse::Ok().json(delivery))
+}
@@ -0,0 +1,11 @@
+pub mod audit;
+pub mod auth;
+pub mod config;
+pub mod error;
+pub mod handlers;
+pub mod metrics;
+pub mod model;
+pub mod routes;
+pub mod storage;
+pub mod validation;
+pub mod webhooks;
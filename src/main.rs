@@ -1,51 +1,55 @@
-mod handlers;
-mod model;
-mod storage;
-
-use actix_web::{web, App, HttpServer};
-use storage::AppState;
+use actix_web::{web, HttpServer};
+use codegang_datasource::config::Config;
+use codegang_datasource::handlers::mcp::Sessions;
+use codegang_datasource::storage::AppState;
+use codegang_datasource::webhooks::{self, Webhooks};
+use codegang_datasource::{auth, handlers, routes};
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    let data_file =
-        std::env::var("DATA_FILE").unwrap_or_else(|_| "codegang-datasource.json".to_string());
-    let state = web::Data::new(AppState::new(data_file));
+    let config = web::Data::new(Config::from_env()?);
+    let state = web::Data::new(AppState::new(&config));
+    let webhooks = web::Data::new(Webhooks::new(&config)?);
     let sessions = web::Data::new(handlers::mcp::new_sessions());
+    handlers::mcp::spawn_change_notifier(state.subscribe(), Sessions::clone(&sessions));
+    webhooks::spawn_dispatcher(state.clone(), webhooks.clone());
+    handlers::mcp::spawn_session_reaper(Sessions::clone(&sessions), config.mcp_session_idle_timeout);
+    auth::jwt::spawn_jwks_refresher(config.clone());
+    let drain_sessions = Sessions::clone(&sessions);
 
     println!("Starting codegang-datasource on http://0.0.0.0:8080");
 
-    HttpServer::new(move || {
-        App::new()
-            .app_data(state.clone())
-            .app_data(sessions.clone())
-            // MCP SSE transport
-            .route("/sse", web::get().to(handlers::mcp::sse_handler))
-            .route("/message", web::post().to(handlers::mcp::message_handler))
-            // Full datasource
-            .route("/api/datasource", web::get().to(handlers::datasource::get))
-            .route("/api/datasource", web::put().to(handlers::datasource::replace))
-            // Services
-            .route("/api/services", web::get().to(handlers::services::list))
-            .route("/api/services", web::post().to(handlers::services::upsert))
-            .route("/api/services/{name}", web::get().to(handlers::services::get))
-            .route("/api/services/{name}", web::delete().to(handlers::services::delete))
-            // Queue contracts
-            .route("/api/queue-contracts", web::get().to(handlers::queue::list))
-            .route("/api/queue-contracts", web::post().to(handlers::queue::upsert))
-            .route("/api/queue-contracts/{topic}", web::get().to(handlers::queue::get))
-            .route("/api/queue-contracts/{topic}", web::delete().to(handlers::queue::delete))
-            // NoSQL contracts
-            .route("/api/nosql-contracts", web::get().to(handlers::nosql::list))
-            .route("/api/nosql-contracts", web::post().to(handlers::nosql::upsert))
-            .route("/api/nosql-contracts/{entity}", web::get().to(handlers::nosql::get))
-            .route("/api/nosql-contracts/{entity}", web::delete().to(handlers::nosql::delete))
-            // Proto contracts
-            .route("/api/proto-contracts", web::get().to(handlers::proto::list))
-            .route("/api/proto-contracts", web::post().to(handlers::proto::upsert))
-            .route("/api/proto-contracts/{name}", web::get().to(handlers::proto::get))
-            .route("/api/proto-contracts/{name}", web::delete().to(handlers::proto::delete))
+    let server = HttpServer::new(move || {
+        routes::app(state.clone(), config.clone(), sessions.clone(), webhooks.clone())
     })
     .bind("0.0.0.0:8080")?
-    .run()
-    .await
+    // Signals are handled below so MCP and event streams can be closed first.
+    .disable_signals()
+    .run();
+
+    let handle = server.handle();
+    actix_web::rt::spawn(async move {
+        shutdown_signal().await;
+        println!("Shutting down: closing MCP sessions and event streams");
+        handlers::mcp::drain(&drain_sessions).await;
+        handlers::events::drain();
+        handle.stop(true).await;
+    });
+
+    server.await
+}
+
+/// Resolves on Ctrl-C or, on Unix, SIGTERM.
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        let mut term = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("failed to install SIGTERM handler");
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {}
+            _ = term.recv() => {}
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = tokio::signal::ctrl_c().await;
 }
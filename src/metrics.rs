@@ -0,0 +1,156 @@
+//! Counters and latency histograms for `/metrics`: HTTP requests per
+//! route, MCP tool calls per tool, and data file writes. Gauges (entities,
+//! MCP sessions) are read when scraped. Rendered in the Prometheus text
+//! exposition format.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use actix_web::web;
+
+use crate::storage::AppState;
+
+/// Upper bounds, in seconds, of the latency histogram buckets.
+const BUCKETS: [f64; 12] = [0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5];
+
+/// Route label for requests that match no route, so that arbitrary paths
+/// don't each get a series.
+const UNMATCHED: &str = "unmatched";
+
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+    /// Observations per bucket, not cumulative; the last is `+Inf`.
+    counts: [u64; BUCKETS.len() + 1],
+    sum: f64,
+}
+
+impl Histogram {
+    fn observe(&mut self, elapsed: Duration) {
+        let seconds = elapsed.as_secs_f64();
+        let bucket = BUCKETS.iter().position(|&le| seconds <= le).unwrap_or(BUCKETS.len());
+        self.counts[bucket] += 1;
+        self.sum += seconds;
+    }
+
+    fn write(&self, out: &mut String, name: &str, labels: &str) {
+        let sep = if labels.is_empty() { "" } else { "," };
+        let mut cumulative = 0;
+        for (i, count) in self.counts.iter().enumerate() {
+            cumulative += count;
+            let le = BUCKETS.get(i).map_or("+Inf".to_string(), f64::to_string);
+            let _ = writeln!(out, "{name}_bucket{{{labels}{sep}le=\"{le}\"}} {cumulative}");
+        }
+        let _ = writeln!(out, "{name}_sum{} {}", braced(labels), self.sum);
+        let _ = writeln!(out, "{name}_count{} {cumulative}", braced(labels));
+    }
+}
+
+#[derive(Debug, Default)]
+struct RouteStats {
+    responses: BTreeMap<u16, u64>,
+    latency: Histogram,
+}
+
+#[derive(Debug, Default)]
+struct WriteStats {
+    latency: Histogram,
+    failures: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct Metrics {
+    /// By method and route pattern.
+    routes: Mutex<BTreeMap<(String, String), RouteStats>>,
+    /// By tool name and whether the call failed.
+    tool_calls: Mutex<BTreeMap<(String, bool), u64>>,
+    writes: Mutex<WriteStats>,
+}
+
+impl Metrics {
+    pub fn observe_request(&self, method: &str, route: &str, status: u16, elapsed: Duration) {
+        let mut routes = self.routes.lock().unwrap();
+        let stats = routes.entry((method.to_string(), route.to_string())).or_default();
+        *stats.responses.entry(status).or_default() += 1;
+        stats.latency.observe(elapsed);
+    }
+
+    pub fn observe_tool_call(&self, tool: &str, failed: bool) {
+        *self.tool_calls.lock().unwrap().entry((tool.to_string(), failed)).or_default() += 1;
+    }
+
+    pub fn observe_write(&self, elapsed: Duration, failed: bool) {
+        let mut writes = self.writes.lock().unwrap();
+        writes.latency.observe(elapsed);
+        if failed {
+            writes.failures += 1;
+        }
+    }
+
+    /// Appends the counters and histograms to `out`.
+    pub fn write(&self, out: &mut String) {
+        let routes = self.routes.lock().unwrap();
+        header(out, "codegang_http_requests_total", "counter", "HTTP responses by method, route and status.");
+        for ((method, route), stats) in routes.iter() {
+            for (status, count) in &stats.responses {
+                let labels = format!("method=\"{method}\",route=\"{}\",status=\"{status}\"", escape(route));
+                let _ = writeln!(out, "codegang_http_requests_total{{{labels}}} {count}");
+            }
+        }
+        header(out, "codegang_http_request_duration_seconds", "histogram", "Time to respond to HTTP requests, until the headers are sent.");
+        for ((method, route), stats) in routes.iter() {
+            let labels = format!("method=\"{method}\",route=\"{}\"", escape(route));
+            stats.latency.write(out, "codegang_http_request_duration_seconds", &labels);
+        }
+        drop(routes);
+
+        header(out, "codegang_mcp_tool_calls_total", "counter", "MCP tool calls by tool and outcome.");
+        for ((tool, failed), count) in self.tool_calls.lock().unwrap().iter() {
+            let outcome = if *failed { "error" } else { "ok" };
+            let _ = writeln!(out, "codegang_mcp_tool_calls_total{{tool=\"{}\",outcome=\"{outcome}\"}} {count}", escape(tool));
+        }
+
+        let writes = self.writes.lock().unwrap();
+        header(out, "codegang_data_file_write_duration_seconds", "histogram", "Time to write the data file.");
+        writes.latency.write(out, "codegang_data_file_write_duration_seconds", "");
+        header(out, "codegang_data_file_write_failures_total", "counter", "Data file writes that failed.");
+        let _ = writeln!(out, "codegang_data_file_write_failures_total {}", writes.failures);
+    }
+}
+
+/// Appends the `# HELP` and `# TYPE` lines of a metric.
+pub fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+/// Escapes a label value.
+pub fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+fn braced(labels: &str) -> String {
+    if labels.is_empty() {
+        String::new()
+    } else {
+        format!("{{{labels}}}")
+    }
+}
+
+/// Middleware that counts and times every request by its route pattern,
+/// e.g. `/api/services/{name}`. Outermost, so rejected requests count too.
+pub async fn track(req: ServiceRequest, next: Next<impl MessageBody>) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let started = Instant::now();
+    let state = req.app_data::<web::Data<AppState>>().cloned();
+    let method = req.method().to_string();
+    let route = req.match_pattern().unwrap_or_else(|| UNMATCHED.to_string());
+    let res = next.call(req).await?;
+    if let Some(state) = state {
+        state.metrics().observe_request(&method, &route, res.status().as_u16(), started.elapsed());
+    }
+    Ok(res)
+}
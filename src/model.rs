@@ -1,9 +1,12 @@
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::error::FieldError;
+
 /// Top-level datasource containing services and all contract registries.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct Datasource {
     #[serde(default)]
     pub services: Vec<ServiceDefinition>,
@@ -17,7 +20,7 @@ pub struct Datasource {
 
 // ── Services ─────────────────────────────────────────────────────
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct ServiceDefinition {
     pub name: String,
     #[serde(rename = "type")]
@@ -36,9 +39,12 @@ pub struct ServiceDefinition {
     pub is_http_server: Option<bool>,
     #[serde(default, skip_serializing_if = "HashMap::is_empty")]
     pub metadata: HashMap<String, String>,
+    /// Teams that own this entity; only their members may change it.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub owners: Vec<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct ServiceQueueConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub publish_queues: Option<Vec<String>>,
@@ -48,7 +54,7 @@ pub struct ServiceQueueConfig {
 
 // ── Queue / Service-Bus contracts ────────────────────────────────
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct QueueContract {
     /// Unique topic/queue name, e.g. "user-registered".
     pub topic_name: String,
@@ -58,10 +64,13 @@ pub struct QueueContract {
     /// The message schema carried on this topic.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub message_schema: Option<MessageSchema>,
+    /// Teams that own this entity; only their members may change it.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub owners: Vec<String>,
 }
 
 /// Language-agnostic description of a message/event payload.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct MessageSchema {
     /// Schema name, e.g. "UserRegisteredEvent".
     pub name: String,
@@ -73,7 +82,7 @@ pub struct MessageSchema {
 }
 
 /// A single field inside a MessageSchema or NosqlSchema.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct SchemaField {
     pub name: String,
     /// Type expressed language-agnostically: string, i64, f64, bool,
@@ -86,7 +95,7 @@ pub struct SchemaField {
 
 // ── NoSQL contracts ──────────────────────────────────────────────
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct NosqlContract {
     /// Unique entity/table identifier, e.g. "BrokerContestSettings".
     pub entity_name: String,
@@ -99,14 +108,215 @@ pub struct NosqlContract {
     /// The entity schema.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub schema: Option<MessageSchema>,
+    /// Teams that own this entity; only their members may change it.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub owners: Vec<String>,
 }
 
 // ── Proto / gRPC contracts ───────────────────────────────────────
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct ProtoContract {
     /// Proto service/file name, e.g. "UsersGrpcService".
     pub name: String,
     /// Raw .proto file content.
     pub raw_proto: String,
+    /// Teams that own this entity; only their members may change it.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub owners: Vec<String>,
+}
+
+// ── Entity kinds and changes ─────────────────────────────────────
+
+/// The kinds of entity stored in the datasource.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EntityKind {
+    Service,
+    QueueContract,
+    NosqlContract,
+    ProtoContract,
+}
+
+impl EntityKind {
+    /// Human-readable name used in error messages.
+    pub fn label(self) -> &'static str {
+        match self {
+            EntityKind::Service => "Service",
+            EntityKind::QueueContract => "Queue contract",
+            EntityKind::NosqlContract => "NoSQL contract",
+            EntityKind::ProtoContract => "Proto contract",
+        }
+    }
+}
+
+/// What every entity kind has in common.
+pub trait Entity {
+    const KIND: EntityKind;
+
+    /// The unique key: name, topic or entity name.
+    fn key(&self) -> &str;
+
+    /// Teams that own the entity; empty when it is unowned.
+    fn owning_teams(&self) -> Vec<&str>;
+}
+
+fn teams(owners: &[String]) -> Vec<&str> {
+    owners.iter().map(String::as_str).collect()
+}
+
+impl Entity for ServiceDefinition {
+    const KIND: EntityKind = EntityKind::Service;
+
+    fn key(&self) -> &str {
+        &self.name
+    }
+
+    /// `owners`, or the `team` metadata for services registered before
+    /// `owners` existed.
+    fn owning_teams(&self) -> Vec<&str> {
+        if self.owners.is_empty() {
+            self.metadata.get("team").map(String::as_str).into_iter().collect()
+        } else {
+            teams(&self.owners)
+        }
+    }
+}
+
+impl Entity for QueueContract {
+    const KIND: EntityKind = EntityKind::QueueContract;
+
+    fn key(&self) -> &str {
+        &self.topic_name
+    }
+
+    fn owning_teams(&self) -> Vec<&str> {
+        teams(&self.owners)
+    }
+}
+
+impl Entity for NosqlContract {
+    const KIND: EntityKind = EntityKind::NosqlContract;
+
+    fn key(&self) -> &str {
+        &self.entity_name
+    }
+
+    fn owning_teams(&self) -> Vec<&str> {
+        teams(&self.owners)
+    }
+}
+
+impl Entity for ProtoContract {
+    const KIND: EntityKind = EntityKind::ProtoContract;
+
+    fn key(&self) -> &str {
+        &self.name
+    }
+
+    fn owning_teams(&self) -> Vec<&str> {
+        teams(&self.owners)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeAction {
+    Created,
+    Updated,
+    Deleted,
+}
+
+/// A single entity change, broadcast by `AppState` once it is saved.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangeEvent {
+    /// Position in the server's sequence of changes, counted from 1 since
+    /// it started.
+    #[serde(default)]
+    pub revision: u64,
+    pub kind: EntityKind,
+    pub key: String,
+    pub action: ChangeAction,
+    /// The entity before the change; absent when it was created.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub before: Option<serde_json::Value>,
+    /// The entity after the change; absent when it was deleted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after: Option<serde_json::Value>,
+}
+
+impl ChangeEvent {
+    /// Top-level fields whose values differ between `before` and `after`.
+    pub fn changed_fields(&self) -> Vec<String> {
+        let before = self.before.as_ref().and_then(serde_json::Value::as_object);
+        let after = self.after.as_ref().and_then(serde_json::Value::as_object);
+        let changed: BTreeSet<&String> = before
+            .into_iter()
+            .chain(after)
+            .flat_map(|map| map.keys())
+            .filter(|name| before.and_then(|m| m.get(*name)) != after.and_then(|m| m.get(*name)))
+            .collect();
+        changed.into_iter().cloned().collect()
+    }
+}
+
+// ── Batch mutations ──────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchOp {
+    Upsert,
+    Delete,
+}
+
+/// A single operation inside a `POST /api/batch` request.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchOperation {
+    pub op: BatchOp,
+    pub kind: EntityKind,
+    /// Entity key; required for `delete`, derived from `value` for `upsert`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    /// Full entity body; required for `upsert`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<serde_json::Value>,
+    /// For `delete`: strip references from services instead of failing.
+    #[serde(default)]
+    pub cascade: bool,
+    /// For `delete`: skip the reference check entirely.
+    #[serde(default)]
+    pub force: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchRequest {
+    pub operations: Vec<BatchOperation>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchOpStatus {
+    /// The operation was applied.
+    Ok,
+    /// The operation itself was invalid.
+    Error,
+    /// The operation was valid but not applied because another one failed.
+    Skipped,
+}
+
+/// Outcome of a single batch operation, in request order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchOpResult {
+    pub index: usize,
+    pub op: BatchOp,
+    pub kind: EntityKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    pub status: BatchOpStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// The fields at fault, addressed from the request body, e.g.
+    /// `/operations/2/value/grpc_servers/0`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
 }
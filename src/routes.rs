@@ -0,0 +1,97 @@
+//! The application: shared state, middleware and every route. Used by the
+//! server binary and by the integration tests.
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceFactory, ServiceRequest, ServiceResponse};
+use actix_web::{middleware, web, App};
+
+use crate::config::Config;
+use crate::handlers::mcp::Sessions;
+use crate::storage::AppState;
+use crate::webhooks::Webhooks;
+use crate::{auth, error, handlers, metrics};
+
+pub fn app(
+    state: web::Data<AppState>,
+    config: web::Data<Config>,
+    sessions: web::Data<Sessions>,
+    webhooks: web::Data<Webhooks>,
+) -> App<
+    impl ServiceFactory<
+        ServiceRequest,
+        Config = (),
+        Response = ServiceResponse<impl MessageBody>,
+        Error = actix_web::Error,
+        InitError = (),
+    >,
+> {
+    App::new()
+        .app_data(state)
+        .app_data(config)
+        .app_data(sessions)
+        .app_data(webhooks)
+        .app_data(web::JsonConfig::default().error_handler(error::json_error_handler))
+        .app_data(web::QueryConfig::default().error_handler(error::query_error_handler))
+        .app_data(web::PathConfig::default().error_handler(error::path_error_handler))
+        .wrap(middleware::from_fn(auth::authenticate))
+        .wrap(middleware::from_fn(metrics::track))
+        // Health and metrics
+        .route("/healthz", web::get().to(handlers::health::healthz))
+        .route("/readyz", web::get().to(handlers::health::readyz))
+        .route("/metrics", web::get().to(handlers::health::metrics))
+        // MCP legacy HTTP+SSE transport
+        .route("/sse", web::get().to(handlers::mcp::legacy::sse_handler))
+        .route("/message", web::post().to(handlers::mcp::legacy::message_handler))
+        // MCP Streamable HTTP transport
+        .route("/mcp", web::post().to(handlers::mcp::streamable::post_handler))
+        .route("/mcp", web::get().to(handlers::mcp::streamable::get_handler))
+        .route("/mcp", web::delete().to(handlers::mcp::streamable::delete_handler))
+        // OAuth discovery for MCP clients
+        .route(
+            "/.well-known/oauth-protected-resource",
+            web::get().to(handlers::oauth::protected_resource),
+        )
+        // Operations
+        .route("/api/admin/mcp-sessions", web::get().to(handlers::admin::mcp_sessions))
+        .route("/api/audit", web::get().to(handlers::audit::list))
+        // Outgoing webhooks
+        .route("/api/webhooks", web::get().to(handlers::webhooks::list))
+        .route("/api/webhooks", web::post().to(handlers::webhooks::create))
+        .route("/api/webhooks/{id}", web::get().to(handlers::webhooks::get))
+        .route("/api/webhooks/{id}", web::put().to(handlers::webhooks::replace))
+        .route("/api/webhooks/{id}", web::delete().to(handlers::webhooks::delete))
+        .route("/api/webhooks/{id}/deliveries", web::get().to(handlers::webhooks::deliveries))
+        .route("/api/webhooks/{id}/test", web::post().to(handlers::webhooks::test))
+        // Live change feed
+        .route("/api/events", web::get().to(handlers::events::stream))
+        // Full datasource
+        .route("/api/datasource", web::get().to(handlers::datasource::get))
+        .route("/api/datasource", web::put().to(handlers::datasource::replace))
+        // Atomic multi-entity mutations
+        .route("/api/batch", web::post().to(handlers::batch::apply))
+        // Services
+        .route("/api/services", web::get().to(handlers::services::list))
+        .route("/api/services", web::post().to(handlers::services::upsert))
+        .route("/api/services/{name}", web::get().to(handlers::services::get))
+        .route("/api/services/{name}", web::delete().to(handlers::services::delete))
+        .route("/api/services/{name}", web::patch().to(handlers::services::patch))
+        // Queue contracts
+        .route("/api/queue-contracts", web::get().to(handlers::queue::list))
+        .route("/api/queue-contracts", web::post().to(handlers::queue::upsert))
+        .route("/api/queue-contracts/{topic}", web::get().to(handlers::queue::get))
+        .route("/api/queue-contracts/{topic}", web::delete().to(handlers::queue::delete))
+        .route("/api/queue-contracts/{topic}", web::patch().to(handlers::queue::patch))
+        // NoSQL contracts
+        .route("/api/nosql-contracts", web::get().to(handlers::nosql::list))
+        .route("/api/nosql-contracts", web::post().to(handlers::nosql::upsert))
+        .route("/api/nosql-contracts/{entity}", web::get().to(handlers::nosql::get))
+        .route("/api/nosql-contracts/{entity}", web::delete().to(handlers::nosql::delete))
+        .route("/api/nosql-contracts/{entity}", web::patch().to(handlers::nosql::patch))
+        // Proto contracts
+        .route("/api/proto-contracts", web::get().to(handlers::proto::list))
+        .route("/api/proto-contracts", web::post().to(handlers::proto::upsert))
+        .route("/api/proto-contracts/{name}", web::get().to(handlers::proto::get))
+        .route("/api/proto-contracts/{name}", web::delete().to(handlers::proto::delete))
+        .route("/api/proto-contracts/{name}", web::patch().to(handlers::proto::patch))
+        .default_service(web::to(error::route_not_found))
+}
@@ -1,39 +1,589 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs::OpenOptions;
 use std::path::{Path, PathBuf};
-use std::sync::RwLock;
+use std::sync::{Mutex, RwLock, RwLockWriteGuard};
+use std::time::Instant;
 
+use tokio::sync::broadcast;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::audit::{Actor, AuditLog, Operation};
+use crate::config::{Config, ReferentialIntegrity};
+use crate::error::{json_pointer, FieldError};
+use crate::metrics::Metrics;
 use crate::model::{
-    Datasource, NosqlContract, ProtoContract, QueueContract, ServiceDefinition,
+    BatchOp, BatchOpResult, BatchOpStatus, BatchOperation, ChangeAction, ChangeEvent, Datasource, Entity, EntityKind,
+    NosqlContract, ProtoContract, QueueContract, ServiceDefinition,
 };
+use crate::validation::Validator;
+
+/// Changes kept for clients resuming the change feed.
+const CHANGE_LOG_LIMIT: usize = 1000;
+
+/// A partial update document for a single entity.
+pub enum PatchDocument {
+    /// RFC 7396 JSON Merge Patch.
+    Merge(serde_json::Value),
+    /// RFC 6902 JSON Patch.
+    Json(json_patch::Patch),
+}
+
+impl PatchDocument {
+    fn apply(&self, target: &mut serde_json::Value) -> Result<(), String> {
+        match self {
+            PatchDocument::Merge(patch) => {
+                json_patch::merge(target, patch);
+                Ok(())
+            }
+            PatchDocument::Json(patch) => json_patch::patch(target, patch).map_err(|e| e.to_string()),
+        }
+    }
+}
+
+pub enum StorageError {
+    NotFound(String),
+    /// The requested change could not be applied.
+    Invalid(String),
+    /// The entity failed validation; each error is addressed by JSON Pointer.
+    Validation(Vec<FieldError>),
+    /// The entity is still referenced by the listed services.
+    Referenced { message: String, services: Vec<String> },
+    /// The caller may not make the change.
+    Forbidden(String),
+    /// No changes are accepted, e.g. because the data file failed to load
+    /// and saving would overwrite it.
+    Unavailable(String),
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StorageError::NotFound(m)
+            | StorageError::Invalid(m)
+            | StorageError::Referenced { message: m, .. }
+            | StorageError::Forbidden(m)
+            | StorageError::Unavailable(m) => f.write_str(m),
+            StorageError::Validation(errors) => {
+                let parts: Vec<String> = errors.iter().map(|e| format!("{}: {}", e.path, e.message)).collect();
+                f.write_str(&parts.join("; "))
+            }
+        }
+    }
+}
+
+/// Applies `patch` to a serialized copy of `entity` and returns the patched
+/// entity. The key returned by `key` must not change.
+fn patch_entity<T, F>(entity: &T, patch: &PatchDocument, key: F) -> Result<T, StorageError>
+where
+    T: Serialize + DeserializeOwned,
+    F: Fn(&T) -> &str,
+{
+    let mut value = serde_json::to_value(entity).map_err(|e| StorageError::Invalid(e.to_string()))?;
+    patch.apply(&mut value).map_err(StorageError::Invalid)?;
+    let patched: T = serde_path_to_error::deserialize(value).map_err(|e| {
+        StorageError::Validation(vec![FieldError::new(json_pointer(e.path()), e.into_inner().to_string())])
+    })?;
+    if key(&patched) != key(entity) {
+        return Err(StorageError::Invalid(format!(
+            "Patch must not change the key '{}'",
+            key(entity)
+        )));
+    }
+    Ok(patched)
+}
+
+/// How a single delete treats services that still reference the entity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeleteMode {
+    /// Fail if the entity is still referenced.
+    Restrict,
+    /// Strip the references from the referencing services.
+    Cascade,
+    /// Delete without checking references.
+    Force,
+}
+
+/// Names of the services whose `subscribe_queues` or `grpc_clients` point at
+/// the given queue or proto contract. Other kinds are never referenced.
+fn referencing_services(ds: &Datasource, kind: EntityKind, key: &str) -> Vec<String> {
+    ds.services
+        .iter()
+        .filter(|s| match kind {
+            EntityKind::QueueContract => s
+                .queue
+                .as_ref()
+                .and_then(|q| q.subscribe_queues.as_ref())
+                .is_some_and(|subs| subs.iter().any(|t| t == key)),
+            EntityKind::ProtoContract => s.grpc_clients.as_ref().is_some_and(|c| c.iter().any(|n| n == key)),
+            EntityKind::Service | EntityKind::NosqlContract => false,
+        })
+        .map(|s| s.name.clone())
+        .collect()
+}
+
+/// Enforces `mode` for deleting `kind`/`key` and, when cascading, removes
+/// the references, which `actor` must be allowed to change every service
+/// for. Returns the changes to the services that were modified.
+fn release_references(
+    ds: &mut Datasource,
+    kind: EntityKind,
+    key: &str,
+    mode: DeleteMode,
+    actor: &Actor,
+) -> Result<Vec<ChangeEvent>, StorageError> {
+    if mode == DeleteMode::Force {
+        return Ok(Vec::new());
+    }
+    let services = referencing_services(ds, kind, key);
+    if services.is_empty() {
+        return Ok(Vec::new());
+    }
+    if mode == DeleteMode::Restrict {
+        return Err(StorageError::Referenced {
+            message: format!(
+                "{} '{key}' is still referenced by: {}",
+                kind.label(),
+                services.join(", ")
+            ),
+            services,
+        });
+    }
+    for svc in ds.services.iter().filter(|s| services.contains(&s.name)) {
+        authorize(actor, Some(svc), Some(svc))?;
+    }
+    let mut events = Vec::new();
+    for svc in ds.services.iter_mut().filter(|s| services.contains(&s.name)) {
+        let before = svc.clone();
+        match kind {
+            EntityKind::QueueContract => {
+                if let Some(subs) = svc.queue.as_mut().and_then(|q| q.subscribe_queues.as_mut()) {
+                    subs.retain(|t| t != key);
+                }
+            }
+            EntityKind::ProtoContract => {
+                if let Some(clients) = svc.grpc_clients.as_mut() {
+                    clients.retain(|n| n != key);
+                }
+            }
+            EntityKind::Service | EntityKind::NosqlContract => {}
+        }
+        events.push(change(Some(&before), Some(&*svc)));
+    }
+    Ok(events)
+}
+
+/// Checks that `actor` may change an entity from `before` to `after`.
+fn authorize<T: Entity>(actor: &Actor, before: Option<&T>, after: Option<&T>) -> Result<(), StorageError> {
+    actor.principal.authorize(before, after).map_err(|e| StorageError::Forbidden(e.detail))
+}
+
+/// Inserts or replaces `item` by key, if `actor` may; returns the change.
+fn upsert_by_key<T: Entity + Serialize>(items: &mut Vec<T>, item: T, actor: &Actor) -> Result<ChangeEvent, StorageError> {
+    let idx = items.iter().position(|i| i.key() == item.key());
+    authorize(actor, idx.map(|idx| &items[idx]), Some(&item))?;
+    let event = change(idx.map(|idx| &items[idx]), Some(&item));
+    match idx {
+        Some(idx) => items[idx] = item,
+        None => items.push(item),
+    }
+    Ok(event)
+}
+
+/// The index of the entity with `key`, if `actor` may delete it.
+fn find_deletable<T: Entity>(items: &[T], key: &str, actor: &Actor) -> Result<usize, StorageError> {
+    let idx = items
+        .iter()
+        .position(|i| i.key() == key)
+        .ok_or_else(|| StorageError::NotFound(format!("{} '{key}' not found", T::KIND.label())))?;
+    authorize(actor, Some(&items[idx]), None)?;
+    Ok(idx)
+}
+
+/// Patches the entity with `key`, if `actor` may make the change; returns
+/// the entity before and after.
+fn patch_by_key<T: Entity + Clone + Serialize + DeserializeOwned>(
+    items: &mut [T],
+    key: &str,
+    patch: &PatchDocument,
+    actor: &Actor,
+    validate: impl Fn(&T) -> Result<(), StorageError>,
+) -> Result<(T, T), StorageError> {
+    let idx = items
+        .iter()
+        .position(|i| i.key() == key)
+        .ok_or_else(|| StorageError::NotFound(format!("{} '{key}' not found", T::KIND.label())))?;
+    let patched = patch_entity(&items[idx], patch, <T as Entity>::key)?;
+    validate(&patched)?;
+    authorize(actor, Some(&items[idx]), Some(&patched))?;
+    let before = std::mem::replace(&mut items[idx], patched.clone());
+    Ok((before, patched))
+}
+
+/// The change taking an entity from `before` to `after`; `None` on one
+/// side means it was created or deleted.
+fn change<T: Entity + Serialize>(before: Option<&T>, after: Option<&T>) -> ChangeEvent {
+    let (action, entity) = match (before, after) {
+        (Some(_), Some(entity)) => (ChangeAction::Updated, entity),
+        (None, Some(entity)) => (ChangeAction::Created, entity),
+        (Some(entity), None) => (ChangeAction::Deleted, entity),
+        (None, None) => unreachable!("a change has an entity on at least one side"),
+    };
+    ChangeEvent {
+        revision: 0,
+        kind: T::KIND,
+        key: entity.key().to_string(),
+        action,
+        before: before.and_then(|e| serde_json::to_value(e).ok()),
+        after: after.and_then(|e| serde_json::to_value(e).ok()),
+    }
+}
+
+fn diff_entities<T: Entity + Serialize + PartialEq>(old: &[T], new: &[T], out: &mut Vec<ChangeEvent>) {
+    for item in new {
+        match old.iter().find(|o| o.key() == item.key()) {
+            None => out.push(change(None, Some(item))),
+            Some(o) if o != item => out.push(change(Some(o), Some(item))),
+            Some(_) => {}
+        }
+    }
+    for item in old {
+        if !new.iter().any(|n| n.key() == item.key()) {
+            out.push(change(Some(item), None));
+        }
+    }
+}
+
+/// Every entity-level difference between two datasources.
+fn diff_datasources(old: &Datasource, new: &Datasource) -> Vec<ChangeEvent> {
+    let mut out = Vec::new();
+    diff_entities(&old.services, &new.services, &mut out);
+    diff_entities(&old.queue_contracts, &new.queue_contracts, &mut out);
+    diff_entities(&old.nosql_contracts, &new.nosql_contracts, &mut out);
+    diff_entities(&old.proto_contracts, &new.proto_contracts, &mut out);
+    out
+}
+
+fn decode_entity<T: DeserializeOwned>(value: &serde_json::Value) -> Result<T, StorageError> {
+    serde_path_to_error::deserialize(value.clone()).map_err(|e| {
+        StorageError::Validation(vec![FieldError::new(json_pointer(e.path()), e.into_inner().to_string())])
+    })
+}
+
+fn validate(validator: &Validator, svc: &ServiceDefinition) -> Result<(), StorageError> {
+    let errors = validator.validate_service(svc);
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(StorageError::Validation(errors))
+    }
+}
+
+/// Applies one batch operation to `ds`, checked against the entities as
+/// earlier operations left them, and returns the affected entity key.
+fn apply_batch_operation(
+    ds: &mut Datasource,
+    op: &BatchOperation,
+    mode: DeleteMode,
+    validator: &Validator,
+    actor: &Actor,
+) -> Result<String, StorageError> {
+    let invalid = |m: String| StorageError::Invalid(m);
+    match op.op {
+        BatchOp::Upsert => {
+            let value = op.value.as_ref().ok_or_else(|| invalid("Upsert requires a 'value'".to_string()))?;
+            let key = match op.kind {
+                EntityKind::Service => {
+                    let svc: ServiceDefinition = decode_entity(value)?;
+                    validate(validator, &svc)?;
+                    let key = svc.name.clone();
+                    upsert_by_key(&mut ds.services, svc, actor)?;
+                    key
+                }
+                EntityKind::QueueContract => {
+                    let qc: QueueContract = decode_entity(value)?;
+                    let key = qc.topic_name.clone();
+                    upsert_by_key(&mut ds.queue_contracts, qc, actor)?;
+                    key
+                }
+                EntityKind::NosqlContract => {
+                    let nc: NosqlContract = decode_entity(value)?;
+                    let key = nc.entity_name.clone();
+                    upsert_by_key(&mut ds.nosql_contracts, nc, actor)?;
+                    key
+                }
+                EntityKind::ProtoContract => {
+                    let pc: ProtoContract = decode_entity(value)?;
+                    let key = pc.name.clone();
+                    upsert_by_key(&mut ds.proto_contracts, pc, actor)?;
+                    key
+                }
+            };
+            match &op.key {
+                Some(k) if *k != key => Err(invalid(format!("Key '{k}' does not match the body key '{key}'"))),
+                _ => Ok(key),
+            }
+        }
+        BatchOp::Delete => {
+            let key = op.key.as_deref().ok_or_else(|| invalid("Delete requires a 'key'".to_string()))?;
+            match op.kind {
+                EntityKind::Service => {
+                    let idx = find_deletable(&ds.services, key, actor)?;
+                    ds.services.remove(idx);
+                }
+                EntityKind::QueueContract => {
+                    let idx = find_deletable(&ds.queue_contracts, key, actor)?;
+                    release_references(ds, op.kind, key, mode, actor)?;
+                    ds.queue_contracts.remove(idx);
+                }
+                EntityKind::NosqlContract => {
+                    let idx = find_deletable(&ds.nosql_contracts, key, actor)?;
+                    ds.nosql_contracts.remove(idx);
+                }
+                EntityKind::ProtoContract => {
+                    let idx = find_deletable(&ds.proto_contracts, key, actor)?;
+                    release_references(ds, op.kind, key, mode, actor)?;
+                    ds.proto_contracts.remove(idx);
+                }
+            }
+            Ok(key.to_string())
+        }
+    }
+}
+
+/// Why a batch was not applied.
+pub enum BatchError {
+    /// The caller may not make operation `index`.
+    Forbidden { index: usize, message: String },
+    /// Some operations failed; the results say which.
+    Rejected(Vec<BatchOpResult>),
+    /// No changes are accepted; see `StorageError::Unavailable`.
+    Unavailable(String),
+}
+
+/// The most recent changes, numbered by revision.
+#[derive(Default)]
+struct ChangeLog {
+    /// Revision of the latest change.
+    revision: u64,
+    recent: VecDeque<ChangeEvent>,
+}
 
 pub struct AppState {
     data: RwLock<Datasource>,
-    file_path: PathBuf,
+    /// `None` for snapshots that are never persisted.
+    file_path: Option<PathBuf>,
+    /// Why an existing data file could not be loaded; the server then
+    /// starts empty and refuses changes, so the file is not overwritten.
+    load_error: Option<String>,
+    referential_integrity: ReferentialIntegrity,
+    validator: Validator,
+    changes: broadcast::Sender<ChangeEvent>,
+    change_log: Mutex<ChangeLog>,
+    /// `None` when auditing is disabled.
+    audit: Option<AuditLog>,
+    metrics: Metrics,
 }
 
 impl AppState {
-    pub fn new(file_path: impl Into<PathBuf>) -> Self {
-        let file_path = file_path.into();
-        let data = Self::load_from_file(&file_path).unwrap_or_else(|| Datasource {
+    /// Loads the data file and opens the audit log. Neither failing stops
+    /// the server: without the data it starts empty and is not ready, and
+    /// without the audit log changes are not audited.
+    pub fn new(config: &Config) -> Self {
+        let file_path = PathBuf::from(&config.data_file);
+        let (data, load_error) = match Self::load_from_file(&file_path) {
+            Ok(data) => (data, None),
+            Err(e) => {
+                eprintln!("Failed to load data file {}: {e}", file_path.display());
+                (None, Some(e))
+            }
+        };
+        let data = data.unwrap_or_else(|| Datasource {
             services: Vec::new(),
             queue_contracts: Vec::new(),
             nosql_contracts: Vec::new(),
             proto_contracts: Vec::new(),
         });
+        let audit = config.audit_log_file.as_ref().and_then(|path| match AuditLog::open(path) {
+            Ok(audit) => Some(audit),
+            Err(e) => {
+                eprintln!("Failed to open audit log {path}, changes will not be audited: {e}");
+                None
+            }
+        });
+        Self {
+            data: RwLock::new(data),
+            file_path: Some(file_path),
+            load_error,
+            referential_integrity: config.referential_integrity,
+            validator: Validator::new(config.allowed_service_types.clone(), config.validation_rules.clone()),
+            changes: broadcast::channel(256).0,
+            change_log: Mutex::default(),
+            audit,
+            metrics: Metrics::default(),
+        }
+    }
+
+    /// An in-memory state over `data` that is never written to disk, e.g. a
+    /// snapshot fetched from a remote server.
+    pub fn in_memory(config: &Config, data: Datasource) -> Self {
         Self {
             data: RwLock::new(data),
-            file_path,
+            file_path: None,
+            load_error: None,
+            referential_integrity: config.referential_integrity,
+            validator: Validator::new(config.allowed_service_types.clone(), config.validation_rules.clone()),
+            changes: broadcast::channel(256).0,
+            change_log: Mutex::default(),
+            audit: None,
+            metrics: Metrics::default(),
+        }
+    }
+
+    /// Resolves the delete mode for one request: `force` wins over
+    /// `cascade`, and without either the server default applies.
+    pub fn delete_mode(&self, cascade: bool, force: bool) -> DeleteMode {
+        if force {
+            return DeleteMode::Force;
+        }
+        if cascade {
+            return DeleteMode::Cascade;
+        }
+        match self.referential_integrity {
+            ReferentialIntegrity::Restrict => DeleteMode::Restrict,
+            ReferentialIntegrity::Cascade => DeleteMode::Cascade,
+            ReferentialIntegrity::Off => DeleteMode::Force,
+        }
+    }
+
+    /// Receives every change made from now on, after it is saved.
+    pub fn subscribe(&self) -> broadcast::Receiver<ChangeEvent> {
+        self.changes.subscribe()
+    }
+
+    /// The current revision, and a receiver of every change after it.
+    pub fn subscribe_at(&self) -> (u64, broadcast::Receiver<ChangeEvent>) {
+        // Changes are numbered and sent under this lock.
+        let log = self.change_log.lock().unwrap();
+        (log.revision, self.changes.subscribe())
+    }
+
+    /// The audit log, unless auditing is disabled.
+    pub fn audit_log(&self) -> Option<&AuditLog> {
+        self.audit.as_ref()
+    }
+
+    /// Saves `data`, then numbers `events`, records them as made by `actor`
+    /// and broadcasts them. Takes the write lock of the change, so writes
+    /// are saved and numbered in the order they were made.
+    fn commit(&self, data: RwLockWriteGuard<'_, Datasource>, actor: &Actor, operation: Operation, mut events: Vec<ChangeEvent>) {
+        self.save(&data);
+        let mut log = self.change_log.lock().unwrap();
+        for event in &mut events {
+            log.revision += 1;
+            event.revision = log.revision;
+        }
+        if let Some(audit) = &self.audit {
+            audit.record(actor, operation, &events);
+        }
+        for event in events {
+            if log.recent.len() == CHANGE_LOG_LIMIT {
+                log.recent.pop_front();
+            }
+            log.recent.push_back(event.clone());
+            // No receivers is fine.
+            let _ = self.changes.send(event);
         }
     }
 
-    fn load_from_file(path: &Path) -> Option<Datasource> {
-        let content = std::fs::read_to_string(path).ok()?;
-        serde_json::from_str(&content).ok()
+    /// Revision of the latest change; 0 before the first.
+    pub fn revision(&self) -> u64 {
+        self.change_log.lock().unwrap().revision
     }
 
-    fn save(&self) {
+    /// The changes after `revision`, oldest first, or `None` if some of
+    /// them are no longer kept or `revision` is in the future.
+    pub fn changes_since(&self, revision: u64) -> Option<Vec<ChangeEvent>> {
+        let log = self.change_log.lock().unwrap();
+        let oldest_kept = log.recent.front().map_or(log.revision + 1, |e| e.revision);
+        if revision > log.revision || revision + 1 < oldest_kept {
+            return None;
+        }
+        Some(log.recent.iter().filter(|e| e.revision > revision).cloned().collect())
+    }
+
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
+    /// Number of entities of each kind.
+    pub fn entity_counts(&self) -> [(EntityKind, usize); 4] {
         let data = self.data.read().unwrap();
-        if let Ok(json) = serde_json::to_string_pretty(&*data) {
-            let _ = std::fs::write(&self.file_path, json);
+        [
+            (EntityKind::Service, data.services.len()),
+            (EntityKind::QueueContract, data.queue_contracts.len()),
+            (EntityKind::NosqlContract, data.nosql_contracts.len()),
+            (EntityKind::ProtoContract, data.proto_contracts.len()),
+        ]
+    }
+
+    /// Checks that the data file was loaded, if it existed, and that it
+    /// can be written.
+    pub fn check_ready(&self) -> Result<(), String> {
+        let Some(file_path) = &self.file_path else { return Ok(()) };
+        if let Some(e) = &self.load_error {
+            return Err(format!("Data file {} could not be loaded: {e}", file_path.display()));
+        }
+        let writable = if file_path.exists() {
+            OpenOptions::new().append(true).open(file_path).map(drop)
+        } else {
+            // Not written yet: try creating a file next to it, as the first
+            // save may be creating the data file itself right now.
+            let name = file_path.file_name().unwrap_or_default().to_string_lossy();
+            let probe = file_path.with_file_name(format!(".{name}.probe"));
+            OpenOptions::new()
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .open(&probe)
+                .and_then(|_| std::fs::remove_file(&probe))
+        };
+        writable.map_err(|e| format!("Data file {} is not writable: {e}", file_path.display()))
+    }
+
+    /// Takes the write lock for a change, unless changes are refused
+    /// because the data file failed to load.
+    fn write_data(&self) -> Result<RwLockWriteGuard<'_, Datasource>, StorageError> {
+        if let (Some(file_path), Some(e)) = (&self.file_path, &self.load_error) {
+            return Err(StorageError::Unavailable(format!(
+                "Data file {} could not be loaded, so no changes are accepted until it is fixed and the server restarted: {e}",
+                file_path.display()
+            )));
+        }
+        Ok(self.data.write().unwrap())
+    }
+
+    /// The data in `path`, or `None` if there is no such file.
+    fn load_from_file(path: &Path) -> Result<Option<Datasource>, String> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.to_string()),
+        };
+        serde_json::from_str(&content).map(Some).map_err(|e| e.to_string())
+    }
+
+    fn save(&self, data: &Datasource) {
+        let Some(file_path) = &self.file_path else { return };
+        let started = Instant::now();
+        let result = serde_json::to_string_pretty(data)
+            .map_err(std::io::Error::from)
+            .and_then(|json| std::fs::write(file_path, json));
+        self.metrics.observe_write(started.elapsed(), result.is_err());
+        if let Err(e) = result {
+            eprintln!("Failed to write data file {}: {e}", file_path.display());
         }
     }
 
@@ -43,11 +593,71 @@ impl AppState {
         self.data.read().unwrap().clone()
     }
 
-    pub fn replace_datasource(&self, ds: Datasource) {
-        let mut data = self.data.write().unwrap();
+    /// Replaces everything after validating every service; errors are
+    /// addressed from the datasource root, e.g. `/services/3/name`.
+    pub fn replace_datasource(&self, ds: Datasource, actor: &Actor) -> Result<(), StorageError> {
+        let errors: Vec<FieldError> = ds
+            .services
+            .iter()
+            .enumerate()
+            .flat_map(|(idx, svc)| {
+                self.validator
+                    .validate_service(svc)
+                    .into_iter()
+                    .map(move |e| FieldError::new(format!("/services/{idx}{}", e.path), e.message))
+            })
+            .collect();
+        if !errors.is_empty() {
+            return Err(StorageError::Validation(errors));
+        }
+        let mut data = self.write_data()?;
+        let events = diff_datasources(&data, &ds);
         *data = ds;
-        drop(data);
-        self.save();
+        self.commit(data, actor, Operation::ReplaceDatasource, events);
+        Ok(())
+    }
+
+    // ── Batch mutations ──────────────────────────────────────────
+
+    /// Applies all operations in order against a copy of the datasource.
+    /// The copy replaces the live data, and is saved once, only if every
+    /// operation succeeds; otherwise nothing changes and `Err` says why.
+    pub fn apply_batch(&self, ops: &[BatchOperation], actor: &Actor) -> Result<Vec<BatchOpResult>, BatchError> {
+        let mut data = self.write_data().map_err(|e| BatchError::Unavailable(e.to_string()))?;
+        let mut working = data.clone();
+        let mut failed = false;
+
+        let mut results = Vec::with_capacity(ops.len());
+        for (index, op) in ops.iter().enumerate() {
+            let mode = self.delete_mode(op.cascade, op.force);
+            let (key, status, error, errors) = match apply_batch_operation(&mut working, op, mode, &self.validator, actor) {
+                Ok(key) => (Some(key), BatchOpStatus::Ok, None, Vec::new()),
+                Err(StorageError::Forbidden(message)) => return Err(BatchError::Forbidden { index, message }),
+                Err(StorageError::Validation(errors)) => {
+                    failed = true;
+                    let prefix = format!("/operations/{index}/value");
+                    let errors: Vec<FieldError> = errors.into_iter().map(|e| e.under(&prefix)).collect();
+                    (op.key.clone(), BatchOpStatus::Error, Some(FieldError::summary(&errors)), errors)
+                }
+                Err(e) => {
+                    failed = true;
+                    (op.key.clone(), BatchOpStatus::Error, Some(e.to_string()), Vec::new())
+                }
+            };
+            results.push(BatchOpResult { index, op: op.op, kind: op.kind, key, status, error, errors });
+        }
+
+        if failed {
+            for r in results.iter_mut().filter(|r| r.status == BatchOpStatus::Ok) {
+                r.status = BatchOpStatus::Skipped;
+            }
+            return Err(BatchError::Rejected(results));
+        }
+
+        let events = diff_datasources(&data, &working);
+        *data = working;
+        self.commit(data, actor, Operation::Batch, events);
+        Ok(results)
     }
 
     // ── Services ─────────────────────────────────────────────────
@@ -60,27 +670,35 @@ impl AppState {
         self.data.read().unwrap().services.iter().find(|s| s.name == name).cloned()
     }
 
-    pub fn upsert_service(&self, svc: ServiceDefinition) {
-        let mut data = self.data.write().unwrap();
-        if let Some(idx) = data.services.iter().position(|s| s.name == svc.name) {
-            data.services[idx] = svc;
-        } else {
-            data.services.push(svc);
-        }
-        drop(data);
-        self.save();
+    pub fn upsert_service(&self, svc: ServiceDefinition, actor: &Actor) -> Result<(), StorageError> {
+        validate(&self.validator, &svc)?;
+        let mut data = self.write_data()?;
+        let event = upsert_by_key(&mut data.services, svc, actor)?;
+        self.commit(data, actor, Operation::Upsert, vec![event]);
+        Ok(())
     }
 
-    pub fn delete_service(&self, name: &str) -> Result<(), String> {
-        let mut data = self.data.write().unwrap();
-        let idx = data.services.iter().position(|s| s.name == name)
-            .ok_or_else(|| format!("Service '{name}' not found"))?;
-        data.services.remove(idx);
-        drop(data);
-        self.save();
+    pub fn delete_service(&self, name: &str, actor: &Actor) -> Result<(), StorageError> {
+        let mut data = self.write_data()?;
+        let idx = find_deletable(&data.services, name, actor)?;
+        let removed = data.services.remove(idx);
+        self.commit(data, actor, Operation::Delete, vec![change(Some(&removed), None)]);
         Ok(())
     }
 
+    pub fn patch_service(
+        &self,
+        name: &str,
+        patch: &PatchDocument,
+        actor: &Actor,
+    ) -> Result<ServiceDefinition, StorageError> {
+        let mut data = self.write_data()?;
+        let (before, patched) =
+            patch_by_key(&mut data.services, name, patch, actor, |svc| validate(&self.validator, svc))?;
+        self.commit(data, actor, Operation::Patch, vec![change(Some(&before), Some(&patched))]);
+        Ok(patched)
+    }
+
     // ── Queue contracts ──────────────────────────────────────────
 
     pub fn get_queue_contracts(&self) -> Vec<QueueContract> {
@@ -91,25 +709,31 @@ impl AppState {
         self.data.read().unwrap().queue_contracts.iter().find(|q| q.topic_name == topic).cloned()
     }
 
-    pub fn upsert_queue_contract(&self, qc: QueueContract) {
-        let mut data = self.data.write().unwrap();
-        if let Some(idx) = data.queue_contracts.iter().position(|q| q.topic_name == qc.topic_name) {
-            data.queue_contracts[idx] = qc;
-        } else {
-            data.queue_contracts.push(qc);
-        }
-        drop(data);
-        self.save();
+    pub fn upsert_queue_contract(&self, qc: QueueContract, actor: &Actor) -> Result<(), StorageError> {
+        let mut data = self.write_data()?;
+        let event = upsert_by_key(&mut data.queue_contracts, qc, actor)?;
+        self.commit(data, actor, Operation::Upsert, vec![event]);
+        Ok(())
     }
 
-    pub fn delete_queue_contract(&self, topic: &str) -> Result<(), String> {
-        let mut data = self.data.write().unwrap();
-        let idx = data.queue_contracts.iter().position(|q| q.topic_name == topic)
-            .ok_or_else(|| format!("Queue contract '{topic}' not found"))?;
-        data.queue_contracts.remove(idx);
-        drop(data);
-        self.save();
-        Ok(())
+    /// Deletes a queue contract, honouring `mode` for services that
+    /// subscribe to it. Returns the services whose references were stripped.
+    pub fn delete_queue_contract(&self, topic: &str, mode: DeleteMode, actor: &Actor) -> Result<Vec<String>, StorageError> {
+        let mut data = self.write_data()?;
+        let idx = find_deletable(&data.queue_contracts, topic, actor)?;
+        let mut events = release_references(&mut data, EntityKind::QueueContract, topic, mode, actor)?;
+        let removed = data.queue_contracts.remove(idx);
+        let updated = events.iter().map(|e| e.key.clone()).collect();
+        events.push(change(Some(&removed), None));
+        self.commit(data, actor, Operation::Delete, events);
+        Ok(updated)
+    }
+
+    pub fn patch_queue_contract(&self, topic: &str, patch: &PatchDocument, actor: &Actor) -> Result<QueueContract, StorageError> {
+        let mut data = self.write_data()?;
+        let (before, patched) = patch_by_key(&mut data.queue_contracts, topic, patch, actor, |_| Ok(()))?;
+        self.commit(data, actor, Operation::Patch, vec![change(Some(&before), Some(&patched))]);
+        Ok(patched)
     }
 
     // ── NoSQL contracts ──────────────────────────────────────────
@@ -122,27 +746,28 @@ impl AppState {
         self.data.read().unwrap().nosql_contracts.iter().find(|n| n.entity_name == entity).cloned()
     }
 
-    pub fn upsert_nosql_contract(&self, nc: NosqlContract) {
-        let mut data = self.data.write().unwrap();
-        if let Some(idx) = data.nosql_contracts.iter().position(|n| n.entity_name == nc.entity_name) {
-            data.nosql_contracts[idx] = nc;
-        } else {
-            data.nosql_contracts.push(nc);
-        }
-        drop(data);
-        self.save();
+    pub fn upsert_nosql_contract(&self, nc: NosqlContract, actor: &Actor) -> Result<(), StorageError> {
+        let mut data = self.write_data()?;
+        let event = upsert_by_key(&mut data.nosql_contracts, nc, actor)?;
+        self.commit(data, actor, Operation::Upsert, vec![event]);
+        Ok(())
     }
 
-    pub fn delete_nosql_contract(&self, entity: &str) -> Result<(), String> {
-        let mut data = self.data.write().unwrap();
-        let idx = data.nosql_contracts.iter().position(|n| n.entity_name == entity)
-            .ok_or_else(|| format!("NoSQL contract '{entity}' not found"))?;
-        data.nosql_contracts.remove(idx);
-        drop(data);
-        self.save();
+    pub fn delete_nosql_contract(&self, entity: &str, actor: &Actor) -> Result<(), StorageError> {
+        let mut data = self.write_data()?;
+        let idx = find_deletable(&data.nosql_contracts, entity, actor)?;
+        let removed = data.nosql_contracts.remove(idx);
+        self.commit(data, actor, Operation::Delete, vec![change(Some(&removed), None)]);
         Ok(())
     }
 
+    pub fn patch_nosql_contract(&self, entity: &str, patch: &PatchDocument, actor: &Actor) -> Result<NosqlContract, StorageError> {
+        let mut data = self.write_data()?;
+        let (before, patched) = patch_by_key(&mut data.nosql_contracts, entity, patch, actor, |_| Ok(()))?;
+        self.commit(data, actor, Operation::Patch, vec![change(Some(&before), Some(&patched))]);
+        Ok(patched)
+    }
+
     // ── Proto contracts ──────────────────────────────────────────
 
     pub fn get_proto_contracts(&self) -> Vec<ProtoContract> {
@@ -153,24 +778,30 @@ impl AppState {
         self.data.read().unwrap().proto_contracts.iter().find(|p| p.name == name).cloned()
     }
 
-    pub fn upsert_proto_contract(&self, pc: ProtoContract) {
-        let mut data = self.data.write().unwrap();
-        if let Some(idx) = data.proto_contracts.iter().position(|p| p.name == pc.name) {
-            data.proto_contracts[idx] = pc;
-        } else {
-            data.proto_contracts.push(pc);
-        }
-        drop(data);
-        self.save();
+    pub fn upsert_proto_contract(&self, pc: ProtoContract, actor: &Actor) -> Result<(), StorageError> {
+        let mut data = self.write_data()?;
+        let event = upsert_by_key(&mut data.proto_contracts, pc, actor)?;
+        self.commit(data, actor, Operation::Upsert, vec![event]);
+        Ok(())
     }
 
-    pub fn delete_proto_contract(&self, name: &str) -> Result<(), String> {
-        let mut data = self.data.write().unwrap();
-        let idx = data.proto_contracts.iter().position(|p| p.name == name)
-            .ok_or_else(|| format!("Proto contract '{name}' not found"))?;
-        data.proto_contracts.remove(idx);
-        drop(data);
-        self.save();
-        Ok(())
+    /// Deletes a proto contract, honouring `mode` for services that use it
+    /// as a gRPC client. Returns the services whose references were stripped.
+    pub fn delete_proto_contract(&self, name: &str, mode: DeleteMode, actor: &Actor) -> Result<Vec<String>, StorageError> {
+        let mut data = self.write_data()?;
+        let idx = find_deletable(&data.proto_contracts, name, actor)?;
+        let mut events = release_references(&mut data, EntityKind::ProtoContract, name, mode, actor)?;
+        let removed = data.proto_contracts.remove(idx);
+        let updated = events.iter().map(|e| e.key.clone()).collect();
+        events.push(change(Some(&removed), None));
+        self.commit(data, actor, Operation::Delete, events);
+        Ok(updated)
+    }
+
+    pub fn patch_proto_contract(&self, name: &str, patch: &PatchDocument, actor: &Actor) -> Result<ProtoContract, StorageError> {
+        let mut data = self.write_data()?;
+        let (before, patched) = patch_by_key(&mut data.proto_contracts, name, patch, actor, |_| Ok(()))?;
+        self.commit(data, actor, Operation::Patch, vec![change(Some(&before), Some(&patched))]);
+        Ok(patched)
     }
 }
@@ -0,0 +1,151 @@
+use std::collections::HashSet;
+
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::error::FieldError;
+use crate::model::ServiceDefinition;
+
+/// Service types accepted when `ALLOWED_SERVICE_TYPES` is not set.
+pub const DEFAULT_SERVICE_TYPES: &[&str] = &["microservice", "http-api", "worker", "frontend", "job"];
+
+/// A deployment-specific rule applied to every upserted service, loaded
+/// from the JSON array in `VALIDATION_RULES_FILE`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CustomRule {
+    /// JSON Pointer into the serialized service, e.g. `/metadata/team`.
+    pub path: String,
+    /// The value at `path` must be present.
+    #[serde(default)]
+    pub required: bool,
+    /// Regex every string at `path` must match (each element, for arrays).
+    #[serde(default, with = "serde_regex")]
+    pub pattern: Option<Regex>,
+    /// Message reported instead of the generated one.
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+mod serde_regex {
+    use regex::Regex;
+    use serde::{Deserialize, Deserializer};
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Option<Regex>, D::Error> {
+        Option::<String>::deserialize(d)?
+            .map(|p| Regex::new(&p).map_err(serde::de::Error::custom))
+            .transpose()
+    }
+}
+
+pub struct Validator {
+    allowed_service_types: Vec<String>,
+    custom_rules: Vec<CustomRule>,
+    repo_url: Regex,
+}
+
+impl Validator {
+    pub fn new(allowed_service_types: Vec<String>, custom_rules: Vec<CustomRule>) -> Self {
+        Self {
+            allowed_service_types,
+            custom_rules,
+            repo_url: Regex::new(r"^https://[A-Za-z0-9.-]+(:[0-9]+)?/[A-Za-z0-9_.-]+/[A-Za-z0-9_.-]+?(\.git)?/?$")
+                .unwrap(),
+        }
+    }
+
+    /// Checks a service definition and returns every violation found, with
+    /// paths relative to the service.
+    pub fn validate_service(&self, svc: &ServiceDefinition) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+
+        if !is_kebab_case(&svc.name) {
+            errors.push(FieldError::new(
+                "/name",
+                format!("'{}' is not kebab-case (lowercase letters, digits and single dashes)", svc.name),
+            ));
+        }
+
+        if !self.allowed_service_types.contains(&svc.service_type) {
+            errors.push(FieldError::new(
+                "/type",
+                format!(
+                    "'{}' is not an allowed service type; expected one of: {}",
+                    svc.service_type,
+                    self.allowed_service_types.join(", ")
+                ),
+            ));
+        }
+
+        if let Some(repo) = &svc.github_repo {
+            if !self.repo_url.is_match(repo) {
+                errors.push(FieldError::new(
+                    "/github_repo",
+                    format!("'{repo}' is not a repository URL like https://github.com/org/repo"),
+                ));
+            }
+        }
+
+        check_duplicates(&mut errors, "/owners", Some(&svc.owners));
+        check_duplicates(&mut errors, "/grpc_servers", svc.grpc_servers.as_deref());
+        check_duplicates(&mut errors, "/grpc_clients", svc.grpc_clients.as_deref());
+        if let Some(queue) = &svc.queue {
+            check_duplicates(&mut errors, "/queue/publish_queues", queue.publish_queues.as_deref());
+            check_duplicates(&mut errors, "/queue/subscribe_queues", queue.subscribe_queues.as_deref());
+        }
+
+        if !self.custom_rules.is_empty() {
+            let value = serde_json::to_value(svc).unwrap_or_default();
+            for rule in &self.custom_rules {
+                apply_custom_rule(&mut errors, rule, &value);
+            }
+        }
+
+        errors
+    }
+}
+
+fn is_kebab_case(s: &str) -> bool {
+    !s.is_empty()
+        && !s.starts_with('-')
+        && !s.ends_with('-')
+        && !s.contains("--")
+        && s.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+fn check_duplicates(errors: &mut Vec<FieldError>, path: &str, items: Option<&[String]>) {
+    let mut seen = HashSet::new();
+    for (idx, item) in items.unwrap_or_default().iter().enumerate() {
+        if !seen.insert(item) {
+            errors.push(FieldError::new(format!("{path}/{idx}"), format!("Duplicate entry '{item}'")));
+        }
+    }
+}
+
+fn apply_custom_rule(errors: &mut Vec<FieldError>, rule: &CustomRule, value: &serde_json::Value) {
+    let message = |default: String| rule.message.clone().unwrap_or(default);
+
+    let target = match value.pointer(&rule.path) {
+        Some(v) if !v.is_null() => v,
+        _ => {
+            if rule.required {
+                errors.push(FieldError::new(&rule.path, message("Value is required".to_string())));
+            }
+            return;
+        }
+    };
+
+    let Some(pattern) = &rule.pattern else { return };
+    let mut check = |path: String, v: &serde_json::Value| match v.as_str() {
+        Some(s) if pattern.is_match(s) => {}
+        Some(s) => errors.push(FieldError::new(path, message(format!("'{s}' does not match {pattern}")))),
+        None => errors.push(FieldError::new(path, message("Expected a string".to_string()))),
+    };
+    match target {
+        serde_json::Value::Array(items) => {
+            for (idx, item) in items.iter().enumerate() {
+                check(format!("{}/{idx}", rule.path), item);
+            }
+        }
+        other => check(rule.path.clone(), other),
+    }
+}
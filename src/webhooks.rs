@@ -0,0 +1,431 @@
+//! Outgoing webhooks: every registry change that matches a subscription is
+//! POSTed to its URL as JSON, signed with an HMAC-SHA256 of the body under
+//! the subscription's secret. Failed deliveries are retried with
+//! exponential backoff, and recent deliveries are kept for the delivery
+//! log. Subscriptions persist to `WEBHOOKS_FILE`.
+
+use std::collections::VecDeque;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime};
+
+use actix_web::web;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::config::Config;
+use crate::error::FieldError;
+use crate::model::{ChangeAction, ChangeEvent, EntityKind};
+use crate::storage::AppState;
+
+/// Deliveries kept for the delivery log, across all webhooks.
+const DELIVERY_LOG_LIMIT: usize = 1000;
+/// How long a receiver has to answer one attempt.
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+/// Longest wait between two attempts.
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+/// A subscription to registry changes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Webhook {
+    /// Assigned by the server.
+    #[serde(default)]
+    pub id: String,
+    /// `http` or `https` URL the payloads are POSTed to.
+    pub url: String,
+    /// Key of the `X-Codegang-Signature` HMAC. Generated when omitted, and
+    /// only returned when the webhook is created.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+    /// Entity kinds to deliver changes for; empty for every kind.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub kinds: Vec<EntityKind>,
+    /// Entity keys (names, topics) to deliver changes for; empty for every entity.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keys: Vec<String>,
+    /// Actions to deliver; empty for every action.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub actions: Vec<ChangeAction>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+impl Webhook {
+    pub fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if !(self.url.starts_with("http://") || self.url.starts_with("https://")) {
+            errors.push(FieldError::new("/url", "must be an http:// or https:// URL"));
+        }
+        if self.secret.as_ref().is_some_and(|s| s.is_empty()) {
+            errors.push(FieldError::new("/secret", "must not be empty"));
+        }
+        errors
+    }
+
+    fn matches(&self, event: &ChangeEvent) -> bool {
+        (self.kinds.is_empty() || self.kinds.contains(&event.kind))
+            && (self.keys.is_empty() || self.keys.contains(&event.key))
+            && (self.actions.is_empty() || self.actions.contains(&event.action))
+    }
+
+    /// The webhook as returned by the API, without its secret.
+    fn redacted(&self) -> Webhook {
+        Webhook {
+            secret: None,
+            ..self.clone()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    /// Not delivered yet; another attempt is scheduled.
+    Pending,
+    Succeeded,
+    /// Every attempt failed.
+    Failed,
+}
+
+/// One POST to the receiver.
+#[derive(Debug, Clone, Serialize)]
+pub struct Attempt {
+    pub timestamp: String,
+    /// HTTP status of the receiver's response, if it answered.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status_code: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub duration_ms: u64,
+}
+
+/// A payload sent, or being sent, to one webhook.
+#[derive(Debug, Clone, Serialize)]
+pub struct Delivery {
+    /// Also sent as `X-Codegang-Delivery`.
+    pub id: String,
+    pub webhook_id: String,
+    /// `<kind>.<action>`, e.g. `queue_contract.updated`, or `ping`.
+    pub event: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    pub status: DeliveryStatus,
+    pub attempts: Vec<Attempt>,
+    /// Why the delivery failed without an attempt.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+pub struct Webhooks {
+    hooks: RwLock<Vec<Webhook>>,
+    /// `None` when subscriptions are not persisted.
+    file_path: Option<PathBuf>,
+    /// Most recent deliveries, oldest first.
+    deliveries: Mutex<VecDeque<Delivery>>,
+    max_attempts: u32,
+    retry_base: Duration,
+}
+
+impl Webhooks {
+    pub fn new(config: &Config) -> std::io::Result<Self> {
+        let file_path = config.webhooks_file.as_ref().map(PathBuf::from);
+        let hooks = match &file_path {
+            Some(path) if path.exists() => {
+                let content = std::fs::read_to_string(path)?;
+                serde_json::from_str(&content).map_err(|e| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidData, format!("{}: {e}", path.display()))
+                })?
+            }
+            _ => Vec::new(),
+        };
+        Ok(Self {
+            hooks: RwLock::new(hooks),
+            file_path,
+            deliveries: Mutex::new(VecDeque::new()),
+            max_attempts: config.webhook_max_attempts.max(1),
+            retry_base: config.webhook_retry_base,
+        })
+    }
+
+    /// Writes the subscriptions to `WEBHOOKS_FILE`. The file holds their
+    /// secrets, so it is created readable by the owner only.
+    fn save(&self) {
+        let Some(file_path) = &self.file_path else { return };
+        let hooks = self.hooks.read().unwrap();
+        if let Ok(json) = serde_json::to_string_pretty(&*hooks) {
+            let mut options = OpenOptions::new();
+            options.write(true).create(true).truncate(true);
+            #[cfg(unix)]
+            std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+            if let Err(e) = options.open(file_path).and_then(|mut file| file.write_all(json.as_bytes())) {
+                eprintln!("Failed to save webhooks to {}: {e}", file_path.display());
+            }
+        }
+    }
+
+    pub fn list(&self) -> Vec<Webhook> {
+        self.hooks.read().unwrap().iter().map(Webhook::redacted).collect()
+    }
+
+    pub fn get(&self, id: &str) -> Option<Webhook> {
+        self.hooks.read().unwrap().iter().find(|h| h.id == id).map(Webhook::redacted)
+    }
+
+    /// Adds `hook` under a new id and returns it with its secret.
+    pub fn create(&self, mut hook: Webhook) -> Webhook {
+        hook.id = uuid::Uuid::new_v4().to_string();
+        if hook.secret.is_none() {
+            hook.secret = Some(uuid::Uuid::new_v4().simple().to_string());
+        }
+        self.hooks.write().unwrap().push(hook.clone());
+        self.save();
+        hook
+    }
+
+    /// Replaces webhook `id`, keeping its secret unless a new one is given.
+    pub fn replace(&self, id: &str, mut hook: Webhook) -> Option<Webhook> {
+        let mut hooks = self.hooks.write().unwrap();
+        let existing = hooks.iter_mut().find(|h| h.id == id)?;
+        hook.id = id.to_string();
+        if hook.secret.is_none() {
+            hook.secret = existing.secret.clone();
+        }
+        *existing = hook;
+        let replaced = existing.redacted();
+        drop(hooks);
+        self.save();
+        Some(replaced)
+    }
+
+    pub fn delete(&self, id: &str) -> bool {
+        let mut hooks = self.hooks.write().unwrap();
+        let before = hooks.len();
+        hooks.retain(|h| h.id != id);
+        let removed = hooks.len() != before;
+        drop(hooks);
+        if removed {
+            self.save();
+        }
+        removed
+    }
+
+    /// Recent deliveries to webhook `id`, newest first.
+    pub fn deliveries(&self, id: &str) -> Vec<Delivery> {
+        self.deliveries.lock().unwrap().iter().rev().filter(|d| d.webhook_id == id).cloned().collect()
+    }
+
+    fn log_delivery(&self, delivery: &Delivery) {
+        let mut deliveries = self.deliveries.lock().unwrap();
+        match deliveries.iter_mut().find(|d| d.id == delivery.id) {
+            Some(existing) => *existing = delivery.clone(),
+            None => {
+                if deliveries.len() == DELIVERY_LOG_LIMIT {
+                    deliveries.pop_front();
+                }
+                deliveries.push_back(delivery.clone());
+            }
+        }
+    }
+
+    /// Sends a `ping` to webhook `id` once, without retries, and returns
+    /// the delivery.
+    pub async fn test(&self, id: &str) -> Option<Delivery> {
+        let hook = self.hooks.read().unwrap().iter().find(|h| h.id == id).cloned()?;
+        Some(self.deliver(&hook, "ping".to_string(), None, 1).await)
+    }
+
+    /// Records that the changes from revision `first` to `last` were lost
+    /// before they could be delivered, as a failed delivery to every webhook.
+    fn record_missed(&self, first: u64, last: u64) {
+        eprintln!("Webhooks fell behind; changes {first} to {last} were not delivered");
+        for hook in self.hooks.read().unwrap().iter() {
+            self.log_delivery(&Delivery {
+                id: uuid::Uuid::new_v4().to_string(),
+                webhook_id: hook.id.clone(),
+                event: "missed".to_string(),
+                key: None,
+                status: DeliveryStatus::Failed,
+                attempts: Vec::new(),
+                error: Some(format!("Changes {first} to {last} were dropped before they could be delivered")),
+            });
+        }
+    }
+
+    /// Sends `event` to `hook`, retrying up to `max_attempts` times.
+    async fn deliver(&self, hook: &Webhook, name: String, event: Option<&ChangeEvent>, max_attempts: u32) -> Delivery {
+        let mut delivery = Delivery {
+            id: uuid::Uuid::new_v4().to_string(),
+            webhook_id: hook.id.clone(),
+            event: name,
+            key: event.map(|e| e.key.clone()),
+            status: DeliveryStatus::Pending,
+            attempts: Vec::new(),
+            error: None,
+        };
+        let body = payload(&delivery, event).to_string();
+        let signature = sign(hook.secret.as_deref().unwrap_or_default(), &body);
+
+        for attempt in 1..=max_attempts {
+            let request = OutgoingRequest {
+                url: hook.url.clone(),
+                event: delivery.event.clone(),
+                delivery_id: delivery.id.clone(),
+                signature: signature.clone(),
+                body: body.clone(),
+            };
+            let result = tokio::task::spawn_blocking(move || request.send()).await;
+            let attempt_result = result.unwrap_or_else(|e| Attempt {
+                timestamp: now(),
+                status_code: None,
+                error: Some(e.to_string()),
+                duration_ms: 0,
+            });
+            let succeeded = attempt_result.error.is_none();
+            delivery.attempts.push(attempt_result);
+            delivery.status = match (succeeded, attempt == max_attempts) {
+                (true, _) => DeliveryStatus::Succeeded,
+                (false, true) => DeliveryStatus::Failed,
+                (false, false) => DeliveryStatus::Pending,
+            };
+            self.log_delivery(&delivery);
+            if delivery.status != DeliveryStatus::Pending {
+                break;
+            }
+            let backoff = self.retry_base.saturating_mul(1 << (attempt - 1).min(16));
+            tokio::time::sleep(backoff.min(MAX_BACKOFF)).await;
+        }
+        delivery
+    }
+}
+
+fn now() -> String {
+    humantime::format_rfc3339_millis(SystemTime::now()).to_string()
+}
+
+/// The JSON body for `delivery`: the change with its before/after
+/// entities and changed fields, or just the envelope for a ping.
+fn payload(delivery: &Delivery, event: Option<&ChangeEvent>) -> serde_json::Value {
+    let mut payload = serde_json::json!({
+        "event": delivery.event,
+        "delivery": delivery.id,
+        "timestamp": now(),
+    });
+    if let (Some(event), Some(obj)) = (event, payload.as_object_mut()) {
+        if let Ok(serde_json::Value::Object(change)) = serde_json::to_value(event) {
+            obj.extend(change);
+        }
+        obj.insert("changed_fields".to_string(), serde_json::json!(event.changed_fields()));
+    }
+    payload
+}
+
+/// `sha256=<hex HMAC-SHA256 of body>`, as sent in `X-Codegang-Signature`.
+pub fn sign(secret: &str, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body.as_bytes());
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// One attempt at a delivery.
+struct OutgoingRequest {
+    url: String,
+    event: String,
+    delivery_id: String,
+    signature: String,
+    body: String,
+}
+
+impl OutgoingRequest {
+    /// POSTs the payload; any response other than 2xx is a failure.
+    fn send(self) -> Attempt {
+        let started = Instant::now();
+        let timestamp = now();
+        let result = ureq::AgentBuilder::new()
+            .timeout(DELIVERY_TIMEOUT)
+            .build()
+            .post(&self.url)
+            .set("Content-Type", "application/json")
+            .set("User-Agent", "codegang-datasource-webhooks")
+            .set("X-Codegang-Event", &self.event)
+            .set("X-Codegang-Delivery", &self.delivery_id)
+            .set("X-Codegang-Signature", &self.signature)
+            .send_string(&self.body);
+        let (status_code, error) = match result {
+            Ok(response) => (Some(response.status()), None),
+            Err(ureq::Error::Status(code, _)) => (Some(code), Some(format!("Receiver answered {code}"))),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        Attempt {
+            timestamp,
+            status_code,
+            error,
+            duration_ms: started.elapsed().as_millis() as u64,
+        }
+    }
+}
+
+/// Delivers every change to `state` to the matching webhooks until the
+/// server stops. Each delivery runs on its own task, so a slow receiver
+/// does not hold up the others. Changes missed by falling behind are
+/// caught up from the change log; those no longer kept there are recorded
+/// as a failed `missed` delivery to every webhook.
+pub fn spawn_dispatcher(state: web::Data<AppState>, webhooks: web::Data<Webhooks>) {
+    let (mut delivered, mut changes) = state.subscribe_at();
+    tokio::spawn(async move {
+        loop {
+            let events = match changes.recv().await {
+                Ok(event) if event.revision > delivered => vec![event],
+                // Already caught up from the change log.
+                Ok(_) => continue,
+                Err(RecvError::Lagged(_)) => match state.changes_since(delivered) {
+                    Some(missed) => missed,
+                    None => {
+                        let latest = state.revision();
+                        webhooks.record_missed(delivered + 1, latest);
+                        delivered = latest;
+                        continue;
+                    }
+                },
+                Err(RecvError::Closed) => break,
+            };
+            for event in events {
+                delivered = event.revision;
+                dispatch(&webhooks, event);
+            }
+        }
+    });
+}
+
+/// Starts delivering `event` to every webhook it matches.
+fn dispatch(webhooks: &web::Data<Webhooks>, event: ChangeEvent) {
+    let targets: Vec<Webhook> = webhooks.hooks.read().unwrap().iter().filter(|h| h.matches(&event)).cloned().collect();
+    for hook in targets {
+        let webhooks = web::Data::clone(webhooks);
+        let event = event.clone();
+        tokio::spawn(async move {
+            let name = format!("{}.{}", event_name(event.kind), action_name(event.action));
+            webhooks.deliver(&hook, name, Some(&event), webhooks.max_attempts).await;
+        });
+    }
+}
+
+fn event_name(kind: EntityKind) -> &'static str {
+    match kind {
+        EntityKind::Service => "service",
+        EntityKind::QueueContract => "queue_contract",
+        EntityKind::NosqlContract => "nosql_contract",
+        EntityKind::ProtoContract => "proto_contract",
+    }
+}
+
+fn action_name(action: ChangeAction) -> &'static str {
+    match action {
+        ChangeAction::Created => "created",
+        ChangeAction::Updated => "updated",
+        ChangeAction::Deleted => "deleted",
+    }
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use actix_web::http::StatusCode;
+use actix_web::test::{self, TestRequest};
+use codegang_datasource::config::Config;
+use codegang_datasource::storage::AppState;
+use common::*;
+
+#[test]
+fn the_audit_log_defaults_to_beside_the_data_file() {
+    let config = Config::from_vars(|name| (name == "DATA_FILE").then(|| "/srv/registry/data.json".to_string())).unwrap();
+    assert_eq!(config.audit_log_file.as_deref(), Some("/srv/registry/codegang-audit.jsonl"));
+}
+
+#[actix_web::test]
+async fn changes_are_audited() {
+    let audit = temp_path("audit.jsonl").display().to_string();
+    let config = config(&[("AUDIT_LOG_FILE", &audit)]);
+    let state = AppState::new(&config);
+    let server = Server::with_state(config, state);
+    let app = test::init_service(server.app()).await;
+
+    let req = TestRequest::post().uri("/api/queue-contracts").set_json(queue_contract("orders")).to_request();
+    assert_eq!(call(&app, req).await.0, StatusCode::OK);
+
+    let (status, body) = call(&app, TestRequest::get().uri("/api/audit").to_request()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body[0]["key"], "orders");
+    assert_eq!(body[0]["action"], "created");
+}
+
+#[actix_web::test]
+async fn an_audit_log_that_cannot_be_opened_disables_auditing() {
+    let audit = temp_path("missing-dir").join("audit.jsonl").display().to_string();
+    let config = config(&[("AUDIT_LOG_FILE", &audit)]);
+    let state = AppState::new(&config);
+    let server = Server::with_state(config, state);
+    let app = test::init_service(server.app()).await;
+
+    let req = TestRequest::post().uri("/api/queue-contracts").set_json(queue_contract("orders")).to_request();
+    assert_eq!(call(&app, req).await.0, StatusCode::OK);
+    let (status, _) = call(&app, TestRequest::get().uri("/api/audit").to_request()).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+/// The `source_ip` audited for one write from `peer` with `forwarded_for`.
+async fn audited_source_ip(trusted_proxies: &str, peer: &str, forwarded_for: &str) -> serde_json::Value {
+    let audit = temp_path("audit.jsonl").display().to_string();
+    let config = config(&[("AUDIT_LOG_FILE", &audit), ("TRUSTED_PROXIES", trusted_proxies)]);
+    let state = AppState::new(&config);
+    let server = Server::with_state(config, state);
+    let app = test::init_service(server.app()).await;
+
+    let req = TestRequest::post()
+        .uri("/api/queue-contracts")
+        .peer_addr(peer.parse().unwrap())
+        .insert_header(("X-Forwarded-For", forwarded_for))
+        .set_json(queue_contract("orders"))
+        .to_request();
+    assert_eq!(call(&app, req).await.0, StatusCode::OK);
+    let (_, body) = call(&app, TestRequest::get().uri("/api/audit").to_request()).await;
+    body[0]["source_ip"].clone()
+}
+
+#[actix_web::test]
+async fn forwarded_addresses_are_believed_only_from_trusted_proxies() {
+    assert_eq!(audited_source_ip("", "198.51.100.7:40000", "203.0.113.9").await, "198.51.100.7");
+    assert_eq!(audited_source_ip("10.0.0.2", "198.51.100.7:40000", "203.0.113.9").await, "198.51.100.7");
+    assert_eq!(audited_source_ip("10.0.0.2", "10.0.0.2:40000", "203.0.113.9").await, "203.0.113.9");
+    // Hops a client added itself, left of the first untrusted one, are ignored.
+    assert_eq!(audited_source_ip("10.0.0.1, 10.0.0.2", "10.0.0.2:40000", "192.0.2.1, 203.0.113.9, 10.0.0.1").await, "203.0.113.9");
+}
+
+#[test]
+fn an_invalid_trusted_proxy_is_a_configuration_error() {
+    let err = Config::from_vars(|name| (name == "TRUSTED_PROXIES").then(|| "10.0.0.0/8".to_string())).err().expect("rejected");
+    assert!(err.to_string().starts_with("TRUSTED_PROXIES=10.0.0.0/8"), "{err}");
+}
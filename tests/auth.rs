@@ -0,0 +1,103 @@
+mod common;
+
+use actix_web::http::{header, StatusCode};
+use actix_web::test::{self, TestRequest};
+use common::*;
+use serde_json::json;
+
+fn empty_datasource() -> serde_json::Value {
+    json!({"services": [], "queue_contracts": [], "nosql_contracts": [], "proto_contracts": []})
+}
+
+#[actix_web::test]
+async fn requests_without_a_valid_key_are_unauthorized() {
+    let server = Server::new(config_with_keys(&[]), empty());
+    let app = test::init_service(server.app()).await;
+
+    let res = test::call_service(&app, TestRequest::get().uri("/api/services").to_request()).await;
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    assert!(res.headers().contains_key(header::WWW_AUTHENTICATE));
+
+    let req = TestRequest::get().uri("/api/services").insert_header(("X-API-Key", "nope")).to_request();
+    let (status, body) = call(&app, req).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["code"], "unauthorized");
+
+    let req = TestRequest::get()
+        .uri("/api/services")
+        .insert_header((header::AUTHORIZATION, format!("Bearer {READ_KEY}")))
+        .to_request();
+    assert_eq!(call(&app, req).await.0, StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn health_probes_need_no_key() {
+    let server = Server::new(config_with_keys(&[]), empty());
+    let app = test::init_service(server.app()).await;
+
+    for uri in ["/healthz", "/readyz", "/h%65althz"] {
+        let (status, _) = call(&app, TestRequest::get().uri(uri).to_request()).await;
+        assert_eq!(status, StatusCode::OK, "{uri}");
+    }
+    let (status, _) = call(&app, TestRequest::get().uri("/metrics").to_request()).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[actix_web::test]
+async fn read_scope_cannot_write() {
+    let server = Server::new(config_with_keys(&[]), empty());
+    let app = test::init_service(server.app()).await;
+
+    let req = TestRequest::post()
+        .uri("/api/services")
+        .insert_header(("X-API-Key", READ_KEY))
+        .set_json(service("billing", &[]))
+        .to_request();
+    let (status, body) = call(&app, req).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["code"], "forbidden");
+
+    let req = TestRequest::post()
+        .uri("/api/services")
+        .insert_header(("X-API-Key", WRITE_KEY))
+        .set_json(service("billing", &[]))
+        .to_request();
+    assert_eq!(call(&app, req).await.0, StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn admin_routes_need_admin_even_when_percent_encoded() {
+    let server = Server::new(config_with_keys(&[]), empty());
+    let app = test::init_service(server.app()).await;
+
+    for uri in ["/api/datasource", "/api/%64atasource", "/api/d%61tasource"] {
+        let req = TestRequest::put()
+            .uri(uri)
+            .insert_header(("X-API-Key", WRITE_KEY))
+            .set_json(empty_datasource())
+            .to_request();
+        assert_eq!(call(&app, req).await.0, StatusCode::FORBIDDEN, "PUT {uri}");
+    }
+    for uri in ["/api/audit", "/api/%61udit", "/api/%77ebhooks", "/api/%61dmin/mcp-sessions"] {
+        let req = TestRequest::get().uri(uri).insert_header(("X-API-Key", WRITE_KEY)).to_request();
+        assert_eq!(call(&app, req).await.0, StatusCode::FORBIDDEN, "GET {uri}");
+    }
+
+    let req = TestRequest::put()
+        .uri("/api/%64atasource")
+        .insert_header(("X-API-Key", ADMIN_KEY))
+        .set_json(empty_datasource())
+        .to_request();
+    assert_eq!(call(&app, req).await.0, StatusCode::OK);
+    let req = TestRequest::get().uri("/api/webhooks").insert_header(("X-API-Key", ADMIN_KEY)).to_request();
+    assert_eq!(call(&app, req).await.0, StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn without_keys_every_request_is_allowed() {
+    let server = Server::new(config(&[]), empty());
+    let app = test::init_service(server.app()).await;
+
+    let req = TestRequest::put().uri("/api/datasource").set_json(empty_datasource()).to_request();
+    assert_eq!(call(&app, req).await.0, StatusCode::OK);
+}
@@ -0,0 +1,129 @@
+mod common;
+
+use actix_web::http::StatusCode;
+use actix_web::test::{self, TestRequest};
+use common::*;
+use serde_json::{json, Value};
+
+fn batch(operations: Value) -> TestRequest {
+    TestRequest::post().uri("/api/batch").set_json(json!({ "operations": operations }))
+}
+
+#[actix_web::test]
+async fn one_failing_operation_leaves_the_data_untouched() {
+    let server = Server::new(config(&[]), empty());
+    let app = test::init_service(server.app()).await;
+
+    let (status, problem) = call(&app, batch(json!([
+        {"op": "upsert", "kind": "queue_contract", "value": queue_contract("orders")},
+        {"op": "upsert", "kind": "service", "key": "billing", "value": {"name": "invoicing", "type": "microservice"}},
+        {"op": "upsert", "kind": "service", "value": {"name": "pricing", "type": "microservice"}},
+    ])).to_request()).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    let results = problem["results"].as_array().unwrap();
+    let statuses: Vec<&str> = results.iter().map(|r| r["status"].as_str().unwrap()).collect();
+    assert_eq!(statuses, ["skipped", "error", "skipped"]);
+    assert!(results[1]["error"].as_str().unwrap().contains("does not match the body key"));
+
+    let data = server.state.get_datasource();
+    assert!(data.queue_contracts.is_empty());
+    assert!(data.services.is_empty());
+}
+
+#[actix_web::test]
+async fn a_batch_applies_every_operation_in_order() {
+    let server = Server::new(config(&[]), empty());
+    let app = test::init_service(server.app()).await;
+
+    let (status, body) = call(&app, batch(json!([
+        {"op": "upsert", "kind": "queue_contract", "value": queue_contract("orders")},
+        {"op": "upsert", "kind": "service", "value": {"name": "billing", "type": "microservice"}},
+        {"op": "upsert", "kind": "service", "key": "billing", "value": {"name": "billing", "type": "microservice", "queue": {"subscribe_queues": ["orders"]}}},
+        {"op": "delete", "kind": "queue_contract", "key": "orders", "cascade": true},
+    ])).to_request()).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    let statuses: Vec<&str> = body["results"].as_array().unwrap().iter().map(|r| r["status"].as_str().unwrap()).collect();
+    assert_eq!(statuses, ["ok", "ok", "ok", "ok"]);
+
+    let data = server.state.get_datasource();
+    assert!(data.queue_contracts.is_empty());
+    assert_eq!(data.services.len(), 1);
+    assert_eq!(data.services[0].queue.as_ref().unwrap().subscribe_queues.as_deref(), Some(&[][..]));
+}
+
+#[actix_web::test]
+async fn invalid_operations_name_their_fields() {
+    let server = Server::new(config(&[]), empty());
+    let app = test::init_service(server.app()).await;
+
+    let (status, problem) = call(&app, batch(json!([
+        {"op": "upsert", "kind": "queue_contract", "value": queue_contract("orders")},
+        {"op": "upsert", "kind": "service", "value": {"name": "billing", "type": "microservice", "grpc_servers": [1]}},
+        {"op": "upsert", "kind": "service", "value": {"name": "pricing", "type": "mainframe"}},
+        {"op": "delete", "kind": "proto_contract", "key": "Missing"},
+    ])).to_request()).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(problem["code"], "batch_rejected");
+
+    let results = problem["results"].as_array().unwrap();
+    let statuses: Vec<&str> = results.iter().map(|r| r["status"].as_str().unwrap()).collect();
+    assert_eq!(statuses, ["skipped", "error", "error", "error"]);
+    assert_eq!(results[1]["errors"][0]["path"], "/operations/1/value/grpc_servers/0");
+    assert_eq!(results[1]["error"], "Field '/operations/1/value/grpc_servers/0' is invalid");
+    assert_eq!(results[2]["errors"][0]["path"], "/operations/2/value/type");
+    assert!(results[3].get("errors").is_none());
+    assert!(results[3]["error"].as_str().unwrap().contains("not found"));
+
+    let paths: Vec<&str> = problem["errors"].as_array().unwrap().iter().map(|e| e["path"].as_str().unwrap()).collect();
+    assert_eq!(paths, ["/operations/1/value/grpc_servers/0", "/operations/2/value/type"]);
+}
+
+#[actix_web::test]
+async fn a_delete_in_a_batch_cascades_only_when_asked() {
+    let server = Server::new(config(&[]), empty());
+    let app = test::init_service(server.app()).await;
+    let setup = batch(json!([
+        {"op": "upsert", "kind": "queue_contract", "value": queue_contract("orders")},
+        {"op": "upsert", "kind": "service", "value": {"name": "billing", "type": "microservice", "queue": {"subscribe_queues": ["orders"]}}},
+    ]));
+    assert_eq!(call(&app, setup.to_request()).await.0, StatusCode::OK);
+
+    let delete = json!([{"op": "delete", "kind": "queue_contract", "key": "orders"}]);
+    let (status, problem) = call(&app, batch(delete).to_request()).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(problem["results"][0]["error"].as_str().unwrap().contains("billing"));
+    assert_eq!(server.state.get_datasource().queue_contracts.len(), 1);
+
+    let delete = json!([{"op": "delete", "kind": "queue_contract", "key": "orders", "cascade": true}]);
+    let (status, body) = call(&app, batch(delete).to_request()).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    let data = server.state.get_datasource();
+    assert!(data.queue_contracts.is_empty());
+    assert_eq!(data.services[0].queue.as_ref().unwrap().subscribe_queues.as_deref(), Some(&[][..]));
+}
+
+#[actix_web::test]
+async fn a_batch_records_one_numbered_change_per_entity() {
+    let server = Server::new(config(&[]), empty());
+    let app = test::init_service(server.app()).await;
+
+    let rejected = json!([
+        {"op": "upsert", "kind": "queue_contract", "value": queue_contract("orders")},
+        {"op": "delete", "kind": "service", "key": "missing"},
+    ]);
+    assert_eq!(call(&app, batch(rejected).to_request()).await.0, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(server.state.revision(), 0);
+
+    let (status, body) = call(&app, batch(json!([
+        {"op": "upsert", "kind": "queue_contract", "value": queue_contract("orders")},
+        {"op": "upsert", "kind": "service", "value": {"name": "billing", "type": "microservice"}},
+        {"op": "upsert", "kind": "service", "key": "billing", "value": {"name": "billing", "type": "microservice", "queue": {"subscribe_queues": ["orders"]}}},
+    ])).to_request()).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+
+    let changes = server.state.changes_since(0).unwrap();
+    let keys: Vec<(u64, &str)> = changes.iter().map(|c| (c.revision, c.key.as_str())).collect();
+    assert_eq!(keys, [(1, "billing"), (2, "orders")]);
+    assert!(changes[0].before.is_none());
+    assert_eq!(changes[0].after.as_ref().unwrap()["queue"]["subscribe_queues"], json!(["orders"]));
+}
@@ -0,0 +1,219 @@
+//! Setup shared by the integration tests: the server's app over in-memory
+//! state, configured from a map instead of the environment.
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::pin::{pin, Pin};
+use std::time::Duration;
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{Service, ServiceFactory, ServiceRequest, ServiceResponse};
+use actix_web::http::StatusCode;
+use actix_web::test::TestRequest;
+use actix_web::{test, web, App};
+use codegang_datasource::audit::Actor;
+use codegang_datasource::auth::{hash_key, Principal};
+use codegang_datasource::config::Config;
+use codegang_datasource::handlers::mcp::{new_sessions, Sessions};
+use codegang_datasource::model::Datasource;
+use codegang_datasource::routes;
+use codegang_datasource::storage::AppState;
+use codegang_datasource::webhooks::Webhooks;
+use serde_json::Value;
+
+pub const READ_KEY: &str = "read-key";
+pub const WRITE_KEY: &str = "write-key";
+pub const ADMIN_KEY: &str = "admin-key";
+/// Member of the `trading` team.
+pub const TRADING_KEY: &str = "trading-key";
+/// Member of the `payments` team.
+pub const PAYMENTS_KEY: &str = "payments-key";
+
+/// A path in the temp directory no other test uses.
+pub fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("codegang-test-{}-{name}", uuid::Uuid::new_v4()))
+}
+
+/// The configuration from `vars`, with nothing written to disk unless they
+/// say so.
+pub fn config(vars: &[(&str, &str)]) -> Config {
+    let mut map: HashMap<String, String> = [("AUDIT_LOG_FILE", ""), ("WEBHOOKS_FILE", "")]
+        .into_iter()
+        .chain(vars.iter().copied())
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    map.entry("DATA_FILE".to_string())
+        .or_insert_with(|| temp_path("data.json").display().to_string());
+    Config::from_vars(|name| map.get(name).cloned()).expect("valid test configuration")
+}
+
+/// An `API_KEYS_FILE` with the keys above.
+pub fn api_keys_file() -> String {
+    let keys: Vec<Value> = [
+        ("reader", READ_KEY, "read"),
+        ("writer", WRITE_KEY, "write"),
+        ("ops", ADMIN_KEY, "admin"),
+        ("trading", TRADING_KEY, "workspace:trading"),
+        ("payments", PAYMENTS_KEY, "workspace:payments"),
+    ]
+    .into_iter()
+    .map(|(name, key, scope)| serde_json::json!({"name": name, "key_sha256": hash_key(key), "scopes": [scope]}))
+    .collect();
+    let path = temp_path("keys.json");
+    std::fs::write(&path, serde_json::to_string(&keys).unwrap()).unwrap();
+    path.display().to_string()
+}
+
+/// The configuration with the keys above required.
+pub fn config_with_keys(vars: &[(&str, &str)]) -> Config {
+    let keys = api_keys_file();
+    let mut vars = vars.to_vec();
+    vars.push(("API_KEYS_FILE", &keys));
+    config(&vars)
+}
+
+/// What the server shares between requests.
+pub struct Server {
+    pub state: web::Data<AppState>,
+    pub config: web::Data<Config>,
+    pub sessions: web::Data<Sessions>,
+    pub webhooks: web::Data<Webhooks>,
+}
+
+impl Server {
+    /// A server over `data` that never writes it to disk.
+    pub fn new(config: Config, data: Datasource) -> Self {
+        let state = AppState::in_memory(&config, data);
+        Self::with_state(config, state)
+    }
+
+    pub fn with_state(config: Config, state: AppState) -> Self {
+        Self {
+            webhooks: web::Data::new(Webhooks::new(&config).unwrap()),
+            state: web::Data::new(state),
+            config: web::Data::new(config),
+            sessions: web::Data::new(new_sessions()),
+        }
+    }
+
+    pub fn app(
+        &self,
+    ) -> App<
+        impl ServiceFactory<
+            ServiceRequest,
+            Config = (),
+            Response = ServiceResponse<impl MessageBody>,
+            Error = actix_web::Error,
+            InitError = (),
+        >,
+    > {
+        routes::app(self.state.clone(), self.config.clone(), self.sessions.clone(), self.webhooks.clone())
+    }
+}
+
+/// Who changes the data when a test calls `AppState` directly.
+pub fn actor() -> Actor {
+    Actor::new(&Principal::anonymous(), None)
+}
+
+pub fn empty() -> Datasource {
+    Datasource {
+        services: Vec::new(),
+        queue_contracts: Vec::new(),
+        nosql_contracts: Vec::new(),
+        proto_contracts: Vec::new(),
+    }
+}
+
+/// Sends `req` and returns the status and the body as JSON, or `Null` if it
+/// is not JSON.
+pub async fn call<S, R, B>(app: &S, req: R) -> (StatusCode, Value)
+where
+    S: Service<R, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    B: MessageBody,
+{
+    let res = test::call_service(app, req).await;
+    let status = res.status();
+    let body = test::read_body(res).await;
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+/// Reads SSE messages from `body` until `count` have arrived, and returns
+/// each as its `event`, `id` and `data` fields.
+pub async fn read_events<B: MessageBody>(body: B, count: usize) -> Vec<(String, String, serde_json::Value)> {
+    let mut body = pin!(body);
+    let mut text = String::new();
+    while text.matches("\n\n").count() < count {
+        let chunk = std::future::poll_fn(|cx| Pin::as_mut(&mut body).poll_next(cx));
+        match tokio::time::timeout(Duration::from_secs(5), chunk).await {
+            Ok(Some(Ok(bytes))) => text.push_str(std::str::from_utf8(&bytes).unwrap()),
+            other => panic!("stream ended after {text:?}: {:?}", other.map(|c| c.map(|r| r.is_ok()))),
+        }
+    }
+    text.split("\n\n")
+        .filter(|message| message.contains("data:"))
+        .map(|message| {
+            let field = |name: &str| {
+                message
+                    .lines()
+                    .find_map(|line| line.strip_prefix(name)?.strip_prefix(": "))
+                    .unwrap_or_default()
+                    .to_string()
+            };
+            (field("event"), field("id"), serde_json::from_str(&field("data")).unwrap())
+        })
+        .collect()
+}
+
+pub fn service(name: &str, owners: &[&str]) -> Value {
+    serde_json::json!({"name": name, "type": "microservice", "owners": owners})
+}
+
+pub fn queue_contract(topic: &str) -> Value {
+    serde_json::json!({"topic_name": topic, "description": "test"})
+}
+
+// ── MCP ──────────────────────────────────────────────────────────
+
+pub fn initialize() -> Value {
+    serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {"protocolVersion": "2025-06-18", "capabilities": {}, "clientInfo": {"name": "test", "version": "1"}},
+    })
+}
+
+/// A JSON-RPC request.
+pub fn rpc(id: u64, method: &str, params: Value) -> Value {
+    serde_json::json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params})
+}
+
+pub fn tool_call(id: u64, name: &str, arguments: Value) -> Value {
+    rpc(id, "tools/call", serde_json::json!({"name": name, "arguments": arguments}))
+}
+
+/// A Streamable HTTP POST by the caller with `key`, in `session` if given.
+pub fn mcp_post(key: &str, session: Option<&str>, message: Value) -> TestRequest {
+    let req = TestRequest::post()
+        .uri("/mcp")
+        .insert_header(("X-API-Key", key))
+        .insert_header(("Accept", "application/json, text/event-stream"))
+        .set_json(message);
+    match session {
+        Some(id) => req.insert_header(("Mcp-Session-Id", id)),
+        None => req,
+    }
+}
+
+/// Sends `initialize` request `req` and returns the new session's id.
+pub async fn open_session<S, R, B>(app: &S, req: R) -> String
+where
+    S: Service<R, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    B: MessageBody,
+{
+    let res = test::call_service(app, req).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    res.headers().get("Mcp-Session-Id").expect("a session id").to_str().unwrap().to_string()
+}
@@ -0,0 +1,66 @@
+mod common;
+
+use actix_web::http::StatusCode;
+use actix_web::test::{self, TestRequest};
+use actix_web::ResponseError;
+use codegang_datasource::error::{ApiError, ErrorCode};
+use common::*;
+use serde_json::{json, Value};
+
+#[actix_web::test]
+async fn a_body_of_the_wrong_shape_names_the_field() {
+    let server = Server::new(config(&[]), empty());
+    let app = test::init_service(server.app()).await;
+
+    let body = json!({"name": "billing", "type": "microservice", "queue": {"publish_queues": ["orders", 7]}});
+    let req = TestRequest::post().uri("/api/services").set_json(body).to_request();
+    let (status, problem) = call(&app, req).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(problem["code"], "invalid_body");
+    assert_eq!(problem["errors"][0]["path"], "/queue/publish_queues/1");
+    assert!(problem["detail"].as_str().unwrap().starts_with("Field '/queue/publish_queues/1' is invalid"), "{problem}");
+
+    let req = TestRequest::post().uri("/api/services").set_json(json!({"type": "microservice"})).to_request();
+    let (status, problem) = call(&app, req).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(problem["errors"][0]["path"], "");
+    assert!(problem["detail"].as_str().unwrap().contains("missing field `name`"), "{problem}");
+}
+
+#[actix_web::test]
+async fn a_body_that_is_not_json_is_rejected() {
+    let server = Server::new(config(&[]), empty());
+    let app = test::init_service(server.app()).await;
+
+    let req = TestRequest::post()
+        .uri("/api/services")
+        .insert_header(("Content-Type", "application/json"))
+        .set_payload("{\"name\": ")
+        .to_request();
+    let (status, problem) = call(&app, req).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(problem["code"], "invalid_body");
+
+    let req = TestRequest::post()
+        .uri("/api/services")
+        .insert_header(("Content-Type", "text/plain"))
+        .set_payload("{}")
+        .to_request();
+    let (status, problem) = call(&app, req).await;
+    assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    assert_eq!(problem["code"], "unsupported_media_type");
+}
+
+#[actix_web::test]
+async fn every_code_is_named_the_same_in_code_type_and_the_readme() {
+    let readme = include_str!("../README.md");
+    for code in ErrorCode::ALL {
+        let res = ApiError::new(code, "detail").error_response();
+        assert_eq!(res.status(), code.status());
+        let body = actix_web::body::to_bytes(res.into_body()).await.unwrap();
+        let problem: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(problem["code"], code.as_str());
+        assert_eq!(problem["type"], format!("urn:codegang:error:{}", code.as_str()));
+        assert!(readme.contains(&format!("| `{}`", code.as_str())), "{} is not documented", code.as_str());
+    }
+}
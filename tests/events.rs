@@ -0,0 +1,77 @@
+mod common;
+
+use actix_web::test::{self, TestRequest};
+use codegang_datasource::storage::AppState;
+use common::*;
+
+#[actix_web::test]
+async fn a_reconnecting_client_gets_what_it_missed() {
+    let server = Server::new(config(&[]), empty());
+    let app = test::init_service(server.app()).await;
+    let publish = |topic: &str| TestRequest::post().uri("/api/queue-contracts").set_json(queue_contract(topic)).to_request();
+
+    let res = test::call_service(&app, TestRequest::get().uri("/api/events").to_request()).await;
+    call(&app, publish("orders")).await;
+    let events = read_events(res.into_body(), 1).await;
+    let (name, last_id, data) = &events[0];
+    assert_eq!(name, "created");
+    assert_eq!(data["revision"], 1);
+    assert_eq!(data["key"], "orders");
+
+    call(&app, publish("orders")).await;
+    call(&app, publish("invoices")).await;
+
+    let req = TestRequest::get().uri("/api/events").insert_header(("Last-Event-ID", last_id.as_str())).to_request();
+    let res = test::call_service(&app, req).await;
+    let missed = read_events(res.into_body(), 2).await;
+    let summary: Vec<_> = missed.iter().map(|(name, _, data)| (name.as_str(), data["revision"].clone(), data["key"].clone())).collect();
+    assert_eq!(
+        summary,
+        [("updated", 2.into(), "orders".into()), ("created", 3.into(), "invoices".into())]
+    );
+}
+
+#[actix_web::test]
+async fn an_unknown_event_id_resets_the_client() {
+    let server = Server::new(config(&[]), empty());
+    let app = test::init_service(server.app()).await;
+    let req = TestRequest::post().uri("/api/queue-contracts").set_json(queue_contract("orders")).to_request();
+    call(&app, req).await;
+
+    for id in ["1-1", "garbage"] {
+        let req = TestRequest::get().uri("/api/events").insert_header(("Last-Event-ID", id)).to_request();
+        let res = test::call_service(&app, req).await;
+        let events = read_events(res.into_body(), 1).await;
+        assert_eq!(events[0].0, "reset", "{id}");
+        assert_eq!(events[0].2["revision"], 1);
+    }
+}
+
+#[actix_web::test]
+async fn concurrent_writes_are_numbered_in_the_order_they_were_made() {
+    // Saved to a file, so each write takes long enough to interleave.
+    let state = std::sync::Arc::new(AppState::new(&config(&[])));
+    let writers: Vec<_> = (0..8)
+        .map(|writer| {
+            let state = state.clone();
+            std::thread::spawn(move || {
+                for i in 0..25 {
+                    let qc = serde_json::from_value(queue_contract(&format!("topic-{writer}-{i}"))).unwrap();
+                    assert!(state.upsert_queue_contract(qc, &actor()).is_ok());
+                }
+            })
+        })
+        .collect();
+    for writer in writers {
+        writer.join().unwrap();
+    }
+
+    let changes = state.changes_since(0).unwrap();
+    let revisions: Vec<u64> = changes.iter().map(|e| e.revision).collect();
+    assert_eq!(revisions, (1..=200).collect::<Vec<_>>());
+    // Each topic is appended when created, so the order they are stored in
+    // is the order they were made.
+    let stored: Vec<String> = state.get_queue_contracts().into_iter().map(|qc| qc.topic_name).collect();
+    let numbered: Vec<String> = changes.into_iter().map(|e| e.key).collect();
+    assert_eq!(stored, numbered);
+}
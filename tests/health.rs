@@ -0,0 +1,86 @@
+mod common;
+
+use actix_web::http::StatusCode;
+use actix_web::test::{self, TestRequest};
+use codegang_datasource::storage::AppState;
+use common::*;
+use serde_json::json;
+
+#[actix_web::test]
+async fn a_data_file_that_fails_to_load_is_never_overwritten() {
+    let data_file = temp_path("broken.json");
+    std::fs::write(&data_file, "{ not json").unwrap();
+    let vars = [("DATA_FILE", data_file.to_str().unwrap())];
+    let server = Server::with_state(config(&vars), AppState::new(&config(&vars)));
+    let app = test::init_service(server.app()).await;
+
+    let (status, body) = call(&app, TestRequest::get().uri("/readyz").to_request()).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body["code"], "not_ready");
+
+    let batch = json!({"operations": [{"op": "upsert", "kind": "service", "value": service("api", &[])}]});
+    let requests = [
+        TestRequest::post().uri("/api/services").set_json(service("api", &[])),
+        TestRequest::post().uri("/api/batch").set_json(batch),
+        TestRequest::put().uri("/api/datasource").set_json(empty()),
+    ];
+    for req in requests {
+        let (status, body) = call(&app, req.to_request()).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE, "{body}");
+        assert_eq!(body["code"], "not_ready");
+    }
+    assert_eq!(std::fs::read_to_string(&data_file).unwrap(), "{ not json");
+}
+
+#[actix_web::test]
+async fn metrics_are_in_the_prometheus_text_format() {
+    let data_file = temp_path("data.json");
+    let vars = [("DATA_FILE", data_file.to_str().unwrap())];
+    let server = Server::with_state(config(&vars), AppState::new(&config(&vars)));
+    let app = test::init_service(server.app()).await;
+    assert_eq!(call(&app, TestRequest::get().uri("/readyz").to_request()).await.0, StatusCode::OK);
+    call(&app, TestRequest::post().uri("/api/services").set_json(service("api", &[])).to_request()).await;
+    for name in ["api", "api", "missing"] {
+        call(&app, TestRequest::get().uri(&format!("/api/services/{name}")).to_request()).await;
+    }
+    let session = open_session(&app, mcp_post(READ_KEY, None, initialize()).to_request()).await;
+    for (id, name) in [(2, "api"), (3, "missing")] {
+        call(&app, mcp_post(READ_KEY, Some(&session), tool_call(id, "get_service", json!({"name": name}))).to_request()).await;
+    }
+
+    let res = test::call_service(&app, TestRequest::get().uri("/metrics").to_request()).await;
+    assert_eq!(res.headers().get("content-type").unwrap(), "text/plain; version=0.0.4; charset=utf-8");
+    let text = String::from_utf8(test::read_body(res).await.to_vec()).unwrap();
+
+    // Every sample belongs to a declared metric and has a numeric value.
+    let mut declared = Vec::new();
+    for line in text.lines() {
+        if let Some(declaration) = line.strip_prefix("# TYPE ") {
+            let (name, kind) = declaration.split_once(' ').unwrap();
+            assert!(["counter", "gauge", "histogram"].contains(&kind), "{line}");
+            declared.push(name.to_string());
+        } else if !line.starts_with("# HELP ") {
+            let (series, value) = line.rsplit_once(' ').unwrap();
+            assert!(value.parse::<f64>().is_ok(), "{line}");
+            let name = series.split('{').next().unwrap();
+            let base = ["_bucket", "_sum", "_count"].iter().find_map(|s| name.strip_suffix(s)).unwrap_or(name);
+            assert!(declared.iter().any(|d| d == name || d == base), "undeclared: {line}");
+        }
+    }
+
+    for sample in [
+        "codegang_http_requests_total{method=\"GET\",route=\"/api/services/{name}\",status=\"200\"} 2",
+        "codegang_http_requests_total{method=\"GET\",route=\"/api/services/{name}\",status=\"404\"} 1",
+        "codegang_http_request_duration_seconds_bucket{method=\"GET\",route=\"/api/services/{name}\",le=\"+Inf\"} 3",
+        "codegang_http_request_duration_seconds_count{method=\"GET\",route=\"/api/services/{name}\"} 3",
+        "codegang_mcp_tool_calls_total{tool=\"get_service\",outcome=\"ok\"} 1",
+        "codegang_mcp_tool_calls_total{tool=\"get_service\",outcome=\"error\"} 1",
+        "codegang_data_file_write_duration_seconds_count 1",
+        "codegang_data_file_write_failures_total 0",
+        "codegang_entities{kind=\"service\"} 1",
+        "codegang_mcp_sessions{transport=\"sse\"} 0",
+        "codegang_mcp_sessions{transport=\"streamable_http\"} 1",
+    ] {
+        assert!(text.lines().any(|line| line == sample), "missing {sample} in\n{text}");
+    }
+}
@@ -0,0 +1,182 @@
+mod common;
+
+use std::pin::{pin, Pin};
+use std::time::Duration;
+
+use actix_web::body::MessageBody;
+use actix_web::http::StatusCode;
+use actix_web::test::{self, TestRequest};
+use codegang_datasource::handlers::mcp::spawn_session_reaper;
+use common::*;
+use serde_json::{json, Value};
+
+#[actix_web::test]
+async fn a_streamable_session_is_only_usable_by_its_caller() {
+    let server = Server::new(config_with_keys(&[]), empty());
+    let app = test::init_service(server.app()).await;
+
+    let session = open_session(&app, mcp_post(WRITE_KEY, None, initialize()).to_request()).await;
+
+    let (status, body) = call(&app, mcp_post(READ_KEY, Some(&session), rpc(2, "tools/list", json!({}))).to_request()).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["code"], "forbidden");
+    for req in [TestRequest::get(), TestRequest::delete()] {
+        let req = req.uri("/mcp").insert_header(("X-API-Key", ADMIN_KEY)).insert_header(("Mcp-Session-Id", session.as_str()));
+        assert_eq!(call(&app, req.to_request()).await.0, StatusCode::FORBIDDEN);
+    }
+
+    let (status, body) = call(&app, mcp_post(WRITE_KEY, Some(&session), rpc(2, "tools/list", json!({}))).to_request()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["result"]["tools"].as_array().is_some_and(|tools| !tools.is_empty()));
+}
+
+#[actix_web::test]
+async fn only_a_successful_initialize_opens_a_session() {
+    let server = Server::new(config_with_keys(&[("MCP_MAX_SESSIONS", "1")]), empty());
+    let app = test::init_service(server.app()).await;
+
+    let mut invalid = initialize();
+    invalid["jsonrpc"] = json!("1.0");
+    for _ in 0..3 {
+        let res = test::call_service(&app, mcp_post(READ_KEY, None, invalid.clone()).to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(res.headers().get("Mcp-Session-Id").is_none());
+        let body: Value = test::read_body_json(res).await;
+        assert_eq!(body["error"]["code"], -32600);
+    }
+
+    let res = test::call_service(&app, mcp_post(READ_KEY, None, initialize()).to_request()).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert!(res.headers().get("Mcp-Session-Id").is_some());
+
+    let (status, body) = call(&app, mcp_post(READ_KEY, None, initialize()).to_request()).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body["code"], "too_many_sessions");
+}
+
+/// The message URL a legacy `GET /sse` stream announces.
+async fn endpoint<B: MessageBody>(body: B) -> String {
+    let mut body = pin!(body);
+    let mut text = String::new();
+    while !text.contains("\n\n") {
+        let chunk = std::future::poll_fn(|cx| Pin::as_mut(&mut body).poll_next(cx));
+        match tokio::time::timeout(Duration::from_secs(5), chunk).await {
+            Ok(Some(Ok(bytes))) => text.push_str(std::str::from_utf8(&bytes).unwrap()),
+            _ => panic!("no endpoint event in {text:?}"),
+        }
+    }
+    text.lines().find_map(|line| line.strip_prefix("data: ")).unwrap().to_string()
+}
+
+#[actix_web::test]
+async fn a_legacy_session_is_only_usable_by_its_caller() {
+    let server = Server::new(config_with_keys(&[]), empty());
+    let app = test::init_service(server.app()).await;
+
+    let res = test::call_service(&app, TestRequest::get().uri("/sse").insert_header(("X-API-Key", WRITE_KEY)).to_request()).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let url = endpoint(res.into_body()).await;
+    let message_url = &url[url.find("/message").unwrap()..];
+    let post = |key: &str| TestRequest::post().uri(message_url).insert_header(("X-API-Key", key)).set_json(initialize());
+
+    let (status, _) = call(&app, TestRequest::post().uri(message_url).set_json(initialize()).to_request()).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(call(&app, post(READ_KEY).to_request()).await.0, StatusCode::FORBIDDEN);
+    assert_eq!(call(&app, post(WRITE_KEY).to_request()).await.0, StatusCode::ACCEPTED);
+}
+
+#[actix_web::test]
+async fn browser_origins_must_be_allowed() {
+    let server = Server::new(config(&[("MCP_ALLOWED_ORIGINS", "https://console.example.test")]), empty());
+    let app = test::init_service(server.app()).await;
+    let from = |origin: &str| {
+        TestRequest::post()
+            .uri("/mcp")
+            .insert_header(("Origin", origin))
+            .set_json(initialize())
+            .to_request()
+    };
+
+    let (status, body) = call(&app, from("http://rebound.example.test")).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["code"], "forbidden");
+    let res = test::call_service(&app, TestRequest::get().uri("/sse").insert_header(("Origin", "http://rebound.example.test")).to_request()).await;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    for origin in ["http://localhost:6274", "http://127.0.0.1:8080", "http://[::1]:3000", "https://console.example.test"] {
+        assert_eq!(call(&app, from(origin)).await.0, StatusCode::OK, "{origin}");
+    }
+    let without_origin = TestRequest::post().uri("/mcp").set_json(initialize()).to_request();
+    assert_eq!(call(&app, without_origin).await.0, StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn idle_sessions_without_a_stream_are_closed() {
+    let server = Server::new(config_with_keys(&[]), empty());
+    // Every session is idle for longer than no time at all.
+    spawn_session_reaper(server.sessions.get_ref().clone(), Duration::ZERO);
+    let app = test::init_service(server.app()).await;
+    let ping = |session: &str| mcp_post(WRITE_KEY, Some(session), rpc(2, "ping", json!({}))).to_request();
+    let idle = open_session(&app, mcp_post(WRITE_KEY, None, initialize()).to_request()).await;
+    let listening = open_session(&app, mcp_post(WRITE_KEY, None, initialize()).to_request()).await;
+    let stream = TestRequest::get().uri("/mcp").insert_header(("X-API-Key", WRITE_KEY)).insert_header(("Mcp-Session-Id", listening.as_str()));
+    let res = test::call_service(&app, stream.to_request()).await;
+
+    let (_, body) = call(&app, TestRequest::get().uri("/api/admin/mcp-sessions").insert_header(("X-API-Key", ADMIN_KEY)).to_request()).await;
+    let listed: Vec<(&str, bool)> = body.as_array().unwrap().iter().map(|s| (s["transport"].as_str().unwrap(), s["stream_open"].as_bool().unwrap())).collect();
+    assert_eq!(listed.len(), 2);
+    assert!(listed.contains(&("streamable_http", true)) && listed.contains(&("streamable_http", false)));
+
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    let (status, body) = call(&app, ping(&idle)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["code"], "session_not_found");
+    assert_eq!(call(&app, ping(&listening)).await.1["result"], json!({}));
+
+    // Disconnecting the stream leaves the session idle.
+    drop(res);
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    assert_eq!(call(&app, ping(&listening)).await.0, StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn a_deleted_session_is_gone() {
+    let server = Server::new(config_with_keys(&[]), empty());
+    let app = test::init_service(server.app()).await;
+    let session = open_session(&app, mcp_post(WRITE_KEY, None, initialize()).to_request()).await;
+
+    let delete = TestRequest::delete().uri("/mcp").insert_header(("X-API-Key", WRITE_KEY)).insert_header(("Mcp-Session-Id", session.as_str()));
+    assert_eq!(call(&app, delete.to_request()).await.0, StatusCode::NO_CONTENT);
+    let (status, body) = call(&app, mcp_post(WRITE_KEY, Some(&session), rpc(2, "ping", json!({}))).to_request()).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["code"], "session_not_found");
+    let (status, body) = call(&app, mcp_post(WRITE_KEY, None, rpc(2, "ping", json!({}))).to_request()).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "missing_session_id");
+}
+
+#[actix_web::test]
+async fn the_endpoint_event_names_the_url_the_client_used() {
+    let announced = |vars: &'static [(&'static str, &'static str)], headers: &'static [(&'static str, &'static str)]| async move {
+        let server = Server::new(config(vars), empty());
+        let app = test::init_service(server.app()).await;
+        let mut req = TestRequest::get().uri("/sse").insert_header(("Host", "10.0.0.5:8080"));
+        for header in headers {
+            req = req.insert_header(*header);
+        }
+        let res = test::call_service(&app, req.to_request()).await;
+        let url = endpoint(res.into_body()).await;
+        url[..url.find("?session_id=").unwrap()].to_string()
+    };
+
+    assert_eq!(announced(&[], &[]).await, "http://10.0.0.5:8080/message");
+    let forwarded = &[("X-Forwarded-Proto", "https"), ("X-Forwarded-Host", "registry.example.test")];
+    assert_eq!(announced(&[], forwarded).await, "https://registry.example.test/message");
+    let rfc7239 = &[("Forwarded", "for=192.0.2.1;proto=https;host=registry.example.test")];
+    assert_eq!(announced(&[], rfc7239).await, "https://registry.example.test/message");
+    assert_eq!(announced(&[("PATH_PREFIX", "registry/")], forwarded).await, "https://registry.example.test/registry/message");
+    let public = &[("PUBLIC_BASE_URL", "https://public.example.test/"), ("PATH_PREFIX", "/registry")];
+    assert_eq!(announced(public, forwarded).await, "https://public.example.test/registry/message");
+    let relative = &[("MCP_RELATIVE_ENDPOINT", "true"), ("PATH_PREFIX", "/registry")];
+    assert_eq!(announced(relative, forwarded).await, "/registry/message");
+}
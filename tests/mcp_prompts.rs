@@ -0,0 +1,60 @@
+mod common;
+
+use actix_web::test::{self, TestRequest};
+use common::*;
+use serde_json::{json, Value};
+
+#[actix_web::test]
+async fn onboarding_embeds_a_summary_of_each_neighbour() {
+    let server = Server::new(config(&[]), empty());
+    let app = test::init_service(server.app()).await;
+    let proto = json!({"name": "Users", "raw_proto": "syntax = \"proto3\";\nservice Users {}\n"});
+    call(&app, TestRequest::post().uri("/api/proto-contracts").set_json(proto).to_request()).await;
+    call(&app, TestRequest::post().uri("/api/queue-contracts").set_json(queue_contract("orders")).to_request()).await;
+    let services = [
+        json!({"name": "engine", "type": "microservice", "grpc_clients": ["Users"], "queue": {"publish_queues": ["orders"]}}),
+        json!({"name": "users", "type": "microservice", "description": "Accounts", "grpc_servers": ["Users"]}),
+        json!({"name": "billing", "type": "worker", "queue": {"subscribe_queues": ["orders"]}}),
+        json!({"name": "unrelated", "type": "microservice"}),
+    ];
+    for svc in services {
+        let (status, body) = call(&app, TestRequest::post().uri("/api/services").set_json(svc).to_request()).await;
+        assert!(status.is_success(), "{body}");
+    }
+    let session = open_session(&app, mcp_post(READ_KEY, None, initialize()).to_request()).await;
+    let send = |message| mcp_post(READ_KEY, Some(&session), message).to_request();
+
+    let (_, body) = call(&app, send(rpc(2, "prompts/list", json!({})))).await;
+    let names: Vec<&str> = body["result"]["prompts"].as_array().unwrap().iter().map(|p| p["name"].as_str().unwrap()).collect();
+    assert_eq!(names, ["implement_queue_consumer", "add_grpc_client", "onboard_to_service"]);
+
+    let (_, body) = call(&app, send(rpc(3, "prompts/get", json!({"name": "onboard_to_service", "arguments": {"service": "engine"}})))).await;
+    let messages = body["result"]["messages"].as_array().unwrap();
+    let text = messages[0]["content"]["text"].as_str().unwrap();
+    assert!(text.contains("Calls over gRPC: users"), "{text}");
+    assert!(text.contains("Consumers of its topics: billing"), "{text}");
+    let embedded: Vec<&str> = messages[1..].iter().map(|m| m["content"]["resource"]["uri"].as_str().unwrap()).collect();
+    assert_eq!(
+        embedded,
+        [
+            "codegang://services/engine",
+            "codegang://proto/Users",
+            "codegang://queues/orders",
+            "codegang://services/users",
+            "codegang://services/billing",
+        ]
+    );
+    let summary = |index: usize| -> Value { serde_json::from_str(messages[index]["content"]["resource"]["text"].as_str().unwrap()).unwrap() };
+    assert_eq!(
+        summary(4),
+        json!({"name": "users", "type": "microservice", "description": "Accounts", "shared_queues": [], "shared_proto_contracts": ["Users"]})
+    );
+    assert_eq!(
+        summary(5),
+        json!({"name": "billing", "type": "worker", "shared_queues": ["orders"], "shared_proto_contracts": []})
+    );
+
+    let (_, body) = call(&app, send(rpc(4, "prompts/get", json!({"name": "implement_queue_consumer", "arguments": {}})))).await;
+    assert_eq!(body["error"]["code"], -32602);
+    assert_eq!(body["error"]["message"], "Missing required argument 'topic'");
+}
@@ -0,0 +1,92 @@
+mod common;
+
+use actix_web::http::StatusCode;
+use actix_web::test::{self, TestRequest};
+use common::*;
+use serde_json::{json, Value};
+
+#[actix_web::test]
+async fn ping_logging_and_completion() {
+    let services = ["billing", "ledger", "bill-printer"].map(|name| service(name, &[]));
+    let server = Server::new(config(&[]), serde_json::from_value(json!({"services": services})).unwrap());
+    let app = test::init_service(server.app()).await;
+    let session = open_session(&app, mcp_post(READ_KEY, None, initialize()).to_request()).await;
+    let send = |message| mcp_post(READ_KEY, Some(&session), message).to_request();
+
+    let (_, body) = call(&app, send(rpc(2, "ping", json!({})))).await;
+    assert_eq!(body["result"], json!({}));
+
+    let reference = json!({"type": "ref/prompt", "name": "onboard_to_service"});
+    let params = json!({"ref": reference, "argument": {"name": "service", "value": "bil"}});
+    let (_, body) = call(&app, send(rpc(3, "completion/complete", params))).await;
+    assert_eq!(body["result"]["completion"], json!({"values": ["bill-printer", "billing"], "total": 2, "hasMore": false}));
+
+    let (_, body) = call(&app, send(rpc(4, "logging/setLevel", json!({"level": "loud"})))).await;
+    assert_eq!(body["error"]["code"], -32602);
+    let (_, body) = call(&app, send(rpc(5, "logging/setLevel", json!({"level": "warning"})))).await;
+    assert_eq!(body["result"], json!({}));
+
+    let stream = TestRequest::get().uri("/mcp").insert_header(("Mcp-Session-Id", session.as_str())).to_request();
+    let res = test::call_service(&app, stream).await;
+    call(&app, send(tool_call(6, "get_service", json!({"name": "missing"})))).await;
+    let events = read_events(res.into_body(), 1).await;
+    let message = &events[0].2;
+    assert_eq!(message["method"], "notifications/message");
+    assert_eq!(message["params"]["level"], "error");
+    assert_eq!(message["params"]["logger"], "tools");
+    assert_eq!(message["params"]["data"], json!({"tool": "get_service", "message": "Service 'missing' not found"}));
+}
+
+#[actix_web::test]
+async fn batches_answer_each_request_in_order() {
+    let server = Server::new(config(&[]), empty());
+    let app = test::init_service(server.app()).await;
+    let session = open_session(&app, mcp_post(READ_KEY, None, initialize()).to_request()).await;
+    let send = |message| mcp_post(READ_KEY, Some(&session), message).to_request();
+    let notification = json!({"jsonrpc": "2.0", "method": "notifications/initialized"});
+
+    let (status, body) = call(&app, send(json!([]))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!((body["id"].clone(), body["error"]["code"].clone()), (Value::Null, json!(-32600)));
+
+    let (status, body) = call(&app, send(json!([notification, notification]))).await;
+    assert_eq!((status, body), (StatusCode::ACCEPTED, Value::Null));
+
+    let batch = json!([
+        rpc(2, "ping", json!({})),
+        {"jsonrpc": "2.0", "id": 3},
+        5,
+        notification,
+        rpc(4, "no/such/method", json!({})),
+        {"jsonrpc": "1.0", "id": 5, "method": "ping"},
+        initialize(),
+    ]);
+    let (status, body) = call(&app, send(batch)).await;
+    assert_eq!(status, StatusCode::OK);
+    let answers: Vec<(Value, Value)> = body
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| (r["id"].clone(), r.get("error").map_or(json!("ok"), |e| e["code"].clone())))
+        .collect();
+    assert_eq!(
+        answers,
+        [
+            (json!(2), json!("ok")),
+            (json!(3), json!(-32600)),
+            (Value::Null, json!(-32600)),
+            (json!(4), json!(-32601)),
+            (json!(5), json!(-32600)),
+            (json!(1), json!(-32600)),
+        ]
+    );
+
+    let req = TestRequest::post()
+        .uri("/mcp")
+        .insert_header(("Mcp-Session-Id", session.as_str()))
+        .insert_header(("Content-Type", "application/json"))
+        .set_payload("[{\"jsonrpc\": \"2.0\",");
+    let (status, body) = call(&app, req.to_request()).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["code"], -32700);
+}
@@ -0,0 +1,70 @@
+mod common;
+
+use actix_web::test::{self, TestRequest};
+use codegang_datasource::handlers::mcp::spawn_change_notifier;
+use common::*;
+use serde_json::{json, Value};
+
+#[actix_web::test]
+async fn entities_are_resources() {
+    let server = Server::new(config(&[]), empty());
+    let app = test::init_service(server.app()).await;
+    let proto = json!({"name": "Users", "raw_proto": "syntax = \"proto3\";\nservice Users {}\n"});
+    call(&app, TestRequest::post().uri("/api/proto-contracts").set_json(proto).to_request()).await;
+    call(&app, TestRequest::post().uri("/api/queue-contracts").set_json(queue_contract("orders")).to_request()).await;
+    let session = open_session(&app, mcp_post(READ_KEY, None, initialize()).to_request()).await;
+    let send = |message| mcp_post(READ_KEY, Some(&session), message).to_request();
+
+    let (_, body) = call(&app, send(rpc(2, "resources/list", json!({})))).await;
+    let listed: Vec<(&str, &str)> = body["result"]["resources"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| (r["uri"].as_str().unwrap(), r["mimeType"].as_str().unwrap()))
+        .collect();
+    assert_eq!(
+        listed,
+        [("codegang://queues/orders", "application/json"), ("codegang://proto/Users", "text/x-protobuf")]
+    );
+
+    let (_, body) = call(&app, send(rpc(3, "resources/read", json!({"uri": "codegang://proto/Users"})))).await;
+    assert_eq!(body["result"]["contents"][0]["text"], "syntax = \"proto3\";\nservice Users {}\n");
+    let (_, body) = call(&app, send(rpc(4, "resources/read", json!({"uri": "codegang://queues/orders"})))).await;
+    let contract: Value = serde_json::from_str(body["result"]["contents"][0]["text"].as_str().unwrap()).unwrap();
+    assert_eq!(contract["topic_name"], "orders");
+    let (_, body) = call(&app, send(rpc(5, "resources/read", json!({"uri": "codegang://queues/missing"})))).await;
+    assert_eq!(body["error"]["code"], -32002);
+}
+
+#[actix_web::test]
+async fn a_subscribed_session_hears_about_changes_to_the_entity() {
+    let server = Server::new(config(&[]), empty());
+    spawn_change_notifier(server.state.subscribe(), server.sessions.get_ref().clone());
+    let app = test::init_service(server.app()).await;
+    let session = open_session(&app, mcp_post(READ_KEY, None, initialize()).to_request()).await;
+    let send = |message| mcp_post(READ_KEY, Some(&session), message).to_request();
+
+    let (_, body) = call(&app, send(rpc(2, "resources/subscribe", json!({"uri": "codegang://queues/orders"})))).await;
+    assert_eq!(body["result"], json!({}));
+    let (_, body) = call(&app, send(rpc(3, "resources/subscribe", json!({"uri": "orders"})))).await;
+    assert_eq!(body["error"]["code"], -32602);
+
+    let stream = TestRequest::get().uri("/mcp").insert_header(("Mcp-Session-Id", session.as_str())).to_request();
+    let res = test::call_service(&app, stream).await;
+    for topic in ["orders", "orders", "invoices"] {
+        call(&app, TestRequest::post().uri("/api/queue-contracts").set_json(queue_contract(topic)).to_request()).await;
+    }
+
+    let events = read_events(res.into_body(), 4).await;
+    let received: Vec<(&str, &Value)> = events.iter().map(|(_, _, data)| (data["method"].as_str().unwrap(), &data["params"])).collect();
+    let updated = json!({"uri": "codegang://queues/orders"});
+    assert_eq!(
+        received,
+        [
+            ("notifications/resources/updated", &updated),
+            ("notifications/resources/list_changed", &json!({})),
+            ("notifications/resources/updated", &updated),
+            ("notifications/resources/list_changed", &json!({})),
+        ]
+    );
+}
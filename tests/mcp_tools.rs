@@ -0,0 +1,167 @@
+mod common;
+
+use actix_web::test::{self, TestRequest};
+use common::*;
+use serde_json::{json, Value};
+
+/// Names of the tools `tools/list` returned in `response`.
+fn tool_names(response: &Value) -> Vec<&str> {
+    response["result"]["tools"].as_array().unwrap().iter().map(|t| t["name"].as_str().unwrap()).collect()
+}
+
+#[actix_web::test]
+async fn write_tools_change_the_registry_only_with_write_access() {
+    let server = Server::new(config_with_keys(&[]), empty());
+    let app = test::init_service(server.app()).await;
+    let writer = open_session(&app, mcp_post(WRITE_KEY, None, initialize()).to_request()).await;
+    let write = |id, name, arguments| mcp_post(WRITE_KEY, Some(&writer), tool_call(id, name, arguments)).to_request();
+
+    let (_, body) = call(&app, write(2, "upsert_queue_contract", queue_contract("orders"))).await;
+    assert_eq!(body["result"]["structuredContent"], json!({"kind": "queue_contract", "key": "orders", "status": "saved"}));
+    let billing = json!({"name": "billing", "type": "microservice", "queue": {"subscribe_queues": ["orders"]}});
+    let (_, body) = call(&app, write(3, "upsert_service", billing)).await;
+    assert_eq!(body["result"]["isError"], false, "{body}");
+
+    let (_, body) = call(&app, write(4, "delete_queue_contract", json!({"topic": "orders"}))).await;
+    assert_eq!(body["result"]["isError"], true);
+    assert!(body["result"]["content"][0]["text"].as_str().unwrap().contains("billing"), "{body}");
+    let (_, body) = call(&app, write(5, "delete_queue_contract", json!({"topic": "orders", "cascade": true}))).await;
+    assert_eq!(body["result"]["structuredContent"]["status"], "deleted");
+    assert_eq!(body["result"]["structuredContent"]["updated_services"], json!(["billing"]));
+    assert!(server.state.get_queue_contract("orders").is_none());
+    assert_eq!(server.state.get_service("billing").unwrap().queue.unwrap().subscribe_queues.unwrap(), Vec::<String>::new());
+
+    let reader = open_session(&app, mcp_post(READ_KEY, None, initialize()).to_request()).await;
+    let (_, body) = call(&app, mcp_post(READ_KEY, Some(&reader), rpc(2, "tools/list", json!({}))).to_request()).await;
+    assert!(!tool_names(&body).iter().any(|name| name.starts_with("upsert_") || name.starts_with("delete_")));
+    let (_, body) = call(&app, mcp_post(READ_KEY, Some(&reader), tool_call(3, "delete_service", json!({"name": "billing"}))).to_request()).await;
+    assert_eq!(body["result"]["isError"], true);
+    assert!(server.state.get_service("billing").is_some());
+}
+
+#[actix_web::test]
+async fn every_tool_describes_its_arguments_and_output() {
+    let server = Server::new(config_with_keys(&[]), empty());
+    let app = test::init_service(server.app()).await;
+    call(&app, TestRequest::post().uri("/api/services").insert_header(("X-API-Key", WRITE_KEY)).set_json(service("api", &[])).to_request()).await;
+    let session = open_session(&app, mcp_post(WRITE_KEY, None, initialize()).to_request()).await;
+    let send = |message| mcp_post(WRITE_KEY, Some(&session), message).to_request();
+
+    let (_, body) = call(&app, send(rpc(2, "tools/list", json!({})))).await;
+    let tools = body["result"]["tools"].as_array().unwrap();
+    assert!(tools.len() > 10);
+    for tool in tools {
+        for schema in ["inputSchema", "outputSchema"] {
+            assert_eq!(tool[schema]["type"], "object", "{} {schema}", tool["name"]);
+            // Self-contained: clients don't resolve references.
+            assert!(!tool[schema].to_string().contains("$ref"), "{} {schema}", tool["name"]);
+        }
+    }
+    let get_service = tools.iter().find(|t| t["name"] == "get_service").unwrap();
+    assert_eq!(get_service["inputSchema"]["required"], json!(["name"]));
+    assert!(get_service["inputSchema"]["properties"]["name"]["description"].is_string());
+
+    let (_, body) = call(&app, send(tool_call(3, "get_service", json!({"name": "api"})))).await;
+    let output = &body["result"]["structuredContent"];
+    for field in get_service["outputSchema"]["required"].as_array().unwrap() {
+        assert!(output.get(field.as_str().unwrap()).is_some(), "{field} missing from {output}");
+    }
+}
+
+#[actix_web::test]
+async fn failures_are_tool_results_the_agent_can_act_on() {
+    let server = Server::new(config_with_keys(&[]), empty());
+    let app = test::init_service(server.app()).await;
+    let session = open_session(&app, mcp_post(WRITE_KEY, None, initialize()).to_request()).await;
+    let send = |message| mcp_post(WRITE_KEY, Some(&session), message).to_request();
+
+    let (_, body) = call(&app, send(tool_call(2, "upsert_service", json!({"name": "api", "type": 5})))).await;
+    assert_eq!(body["result"]["isError"], true);
+    let text = body["result"]["content"][0]["text"].as_str().unwrap();
+    assert!(text.starts_with("Invalid arguments for upsert_service: type: invalid type"), "{text}");
+    assert!(body["result"].get("structuredContent").is_none());
+
+    let (_, body) = call(&app, send(tool_call(3, "get_service", json!({"name": "missing"})))).await;
+    assert_eq!(body["result"]["isError"], true);
+    assert_eq!(body["result"]["content"][0]["text"], "Service 'missing' not found");
+
+    let (_, body) = call(&app, send(tool_call(4, "no_such_tool", json!({})))).await;
+    assert_eq!(body["error"]["code"], -32602);
+
+    let (_, body) = call(&app, send(tool_call(5, "upsert_service", service("api", &[])))).await;
+    let result = &body["result"];
+    assert_eq!(result["isError"], false);
+    let text: Value = serde_json::from_str(result["content"][0]["text"].as_str().unwrap()).unwrap();
+    assert_eq!(text, result["structuredContent"]);
+}
+
+#[actix_web::test]
+async fn lists_are_paged_within_the_token_budget() {
+    let topics = ["q0", "q1", "q2", "q3", "q4"];
+    let services = ["s0", "s1", "s2", "s3", "s4"].map(|name| service(name, &[]));
+    let data = json!({"services": services, "queue_contracts": topics.map(queue_contract)});
+    let server = Server::new(config(&[]), serde_json::from_value(data).unwrap());
+    let app = test::init_service(server.app()).await;
+    let session = open_session(&app, mcp_post(READ_KEY, None, initialize()).to_request()).await;
+    let send = |message| mcp_post(READ_KEY, Some(&session), message).to_request();
+
+    // Each entity is over budget, so every page holds exactly one.
+    let mut seen = Vec::new();
+    let mut cursor = Value::Null;
+    for id in 2.. {
+        let args = json!({"detail": "summary", "max_tokens": 1, "cursor": cursor});
+        let (_, body) = call(&app, send(tool_call(id, "list_queue_contracts", args))).await;
+        let page = &body["result"]["structuredContent"];
+        assert_eq!(page["total"], 5);
+        assert_eq!(page["items"].as_array().unwrap().len(), 1);
+        assert_eq!(page["items"][0], json!({"topic_name": topics[seen.len()], "description": "test"}));
+        seen.push(page["items"][0]["topic_name"].clone());
+        cursor = page["next_cursor"].clone();
+        if cursor.is_null() {
+            break;
+        }
+    }
+    assert_eq!(seen, topics);
+
+    let (_, body) = call(&app, send(tool_call(10, "get_datasource", json!({"max_tokens": 1, "cursor": "5"})))).await;
+    let page = &body["result"]["structuredContent"];
+    assert_eq!(page["services"], json!([]));
+    assert_eq!(page["queue_contracts"][0]["topic_name"], "q0");
+    assert_eq!((page["total"].as_u64(), page["next_cursor"].as_str()), (Some(10), Some("6")));
+
+    let (_, body) = call(&app, send(tool_call(11, "list_services", json!({"cursor": "99"})))).await;
+    assert_eq!(body["result"]["isError"], true);
+    assert_eq!(body["result"]["content"][0]["text"], "Invalid cursor '99'");
+}
+
+#[actix_web::test]
+async fn selecting_a_workspace_changes_only_that_session_s_tools() {
+    let services = [service("billing", &["payments"]), service("ledger", &["trading"])];
+    let server = Server::new(config(&[]), serde_json::from_value(json!({"services": services})).unwrap());
+    let app = test::init_service(server.app()).await;
+    let chosen = open_session(&app, mcp_post(READ_KEY, None, initialize()).to_request()).await;
+    let other = open_session(&app, mcp_post(READ_KEY, None, initialize()).to_request()).await;
+    let send = |session: &str, message| mcp_post(READ_KEY, Some(session), message).to_request();
+
+    let (_, body) = call(&app, send(&chosen, rpc(2, "tools/list", json!({})))).await;
+    assert!(!tool_names(&body).contains(&"get_workspace"));
+    let stream = TestRequest::get().uri("/mcp").insert_header(("Mcp-Session-Id", chosen.as_str())).to_request();
+    let res = test::call_service(&app, stream).await;
+
+    let (_, body) = call(&app, send(&chosen, tool_call(3, "select_workspace", json!({"workspace": "marketing"})))).await;
+    assert_eq!(body["result"]["content"][0]["text"], "Unknown workspace 'marketing'; available: payments, trading");
+    let (_, body) = call(&app, send(&chosen, tool_call(4, "select_workspace", json!({"workspace": "payments"})))).await;
+    assert_eq!(body["result"]["structuredContent"], json!({"workspace": "payments", "available": ["payments", "trading"]}));
+    let events = read_events(res.into_body(), 1).await;
+    assert_eq!(events[0].2["method"], "notifications/tools/list_changed");
+
+    let (_, body) = call(&app, send(&chosen, rpc(5, "tools/list", json!({})))).await;
+    assert!(tool_names(&body).contains(&"get_workspace"));
+    let (_, body) = call(&app, send(&chosen, tool_call(6, "get_workspace", json!({})))).await;
+    assert_eq!(body["result"]["structuredContent"]["services"], json!([{"name": "billing", "type": "microservice", "owners": ["payments"]}]));
+
+    let (_, body) = call(&app, send(&other, rpc(2, "tools/list", json!({})))).await;
+    assert!(!tool_names(&body).contains(&"get_workspace"));
+    let (_, body) = call(&app, send(&other, tool_call(3, "get_workspace", json!({})))).await;
+    assert_eq!(body["result"]["content"][0]["text"], "get_workspace needs a workspace; call select_workspace first");
+}
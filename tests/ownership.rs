@@ -0,0 +1,98 @@
+mod common;
+
+use actix_web::http::StatusCode;
+use actix_web::test::{self, TestRequest};
+use common::*;
+use serde_json::{json, Value};
+
+fn post(uri: &str, key: &str, body: Value) -> TestRequest {
+    TestRequest::post().uri(uri).insert_header(("X-API-Key", key)).set_json(body)
+}
+
+fn merge_patch(uri: &str, key: &str, body: Value) -> TestRequest {
+    TestRequest::patch()
+        .uri(uri)
+        .insert_header(("X-API-Key", key))
+        .insert_header(("Content-Type", "application/merge-patch+json"))
+        .set_payload(body.to_string())
+}
+
+fn owners(server: &Server, name: &str) -> Vec<String> {
+    server.state.get_service(name).expect("service exists").owners
+}
+
+#[actix_web::test]
+async fn a_team_changes_only_what_it_owns() {
+    let server = Server::new(config_with_keys(&[]), empty());
+    let app = test::init_service(server.app()).await;
+
+    let (status, _) = call(&app, post("/api/services", TRADING_KEY, service("pricing", &["trading"])).to_request()).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = call(&app, post("/api/services", TRADING_KEY, service("ledger", &["payments"])).to_request()).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    for key in [PAYMENTS_KEY, WRITE_KEY] {
+        let req = merge_patch("/api/services/pricing", key, json!({"description": "mine now"})).to_request();
+        assert_eq!(call(&app, req).await.0, StatusCode::FORBIDDEN, "{key}");
+        let req = TestRequest::delete().uri("/api/services/pricing").insert_header(("X-API-Key", key)).to_request();
+        assert_eq!(call(&app, req).await.0, StatusCode::FORBIDDEN, "{key}");
+    }
+
+    let req = merge_patch("/api/services/pricing", TRADING_KEY, json!({"description": "Prices"})).to_request();
+    assert_eq!(call(&app, req).await.0, StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn a_team_cannot_hand_its_entity_to_another() {
+    let server = Server::new(config_with_keys(&[]), empty());
+    let app = test::init_service(server.app()).await;
+    call(&app, post("/api/services", TRADING_KEY, service("pricing", &["trading"])).to_request()).await;
+
+    let (status, body) = call(&app, post("/api/services", TRADING_KEY, service("pricing", &["payments"])).to_request()).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["code"], "forbidden");
+
+    let req = merge_patch("/api/services/pricing", TRADING_KEY, json!({"owners": ["payments"]})).to_request();
+    assert_eq!(call(&app, req).await.0, StatusCode::FORBIDDEN);
+
+    let req = merge_patch("/api/services/pricing", TRADING_KEY, json!({"owners": []})).to_request();
+    assert_eq!(call(&app, req).await.0, StatusCode::FORBIDDEN);
+
+    let req = TestRequest::patch()
+        .uri("/api/services/pricing")
+        .insert_header(("X-API-Key", TRADING_KEY))
+        .insert_header(("Content-Type", "application/json-patch+json"))
+        .set_payload(json!([{"op": "replace", "path": "/owners/0", "value": "payments"}]).to_string())
+        .to_request();
+    assert_eq!(call(&app, req).await.0, StatusCode::FORBIDDEN);
+    assert_eq!(owners(&server, "pricing"), ["trading"]);
+
+    // Sharing with another team keeps it owned by one of the caller's.
+    let req = merge_patch("/api/services/pricing", TRADING_KEY, json!({"owners": ["trading", "payments"]})).to_request();
+    assert_eq!(call(&app, req).await.0, StatusCode::OK);
+
+    let req = merge_patch("/api/services/pricing", ADMIN_KEY, json!({"owners": ["payments"]})).to_request();
+    assert_eq!(call(&app, req).await.0, StatusCode::OK);
+    assert_eq!(owners(&server, "pricing"), ["payments"]);
+}
+
+#[actix_web::test]
+async fn a_batch_with_a_forbidden_operation_applies_nothing() {
+    let server = Server::new(config_with_keys(&[]), empty());
+    let app = test::init_service(server.app()).await;
+    call(&app, post("/api/services", PAYMENTS_KEY, service("ledger", &["payments"])).to_request()).await;
+
+    let batch = json!({"operations": [
+        {"op": "upsert", "kind": "service", "value": service("pricing", &["trading"])},
+        {"op": "upsert", "kind": "service", "value": service("ledger", &["trading"])},
+    ]});
+    let (status, body) = call(&app, post("/api/batch", TRADING_KEY, batch).to_request()).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert!(body["detail"].as_str().unwrap().starts_with("Operation 1:"), "{body}");
+    assert!(server.state.get_service("pricing").is_none());
+    assert_eq!(owners(&server, "ledger"), ["payments"]);
+
+    let batch = json!({"operations": [{"op": "delete", "kind": "service", "key": "ledger"}]});
+    assert_eq!(call(&app, post("/api/batch", TRADING_KEY, batch).to_request()).await.0, StatusCode::FORBIDDEN);
+    assert!(server.state.get_service("ledger").is_some());
+}
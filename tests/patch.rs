@@ -0,0 +1,111 @@
+mod common;
+
+use actix_web::http::StatusCode;
+use actix_web::test::{self, TestRequest};
+use common::*;
+use serde_json::{json, Value};
+
+fn patch(uri: &str, content_type: &str, body: Value) -> TestRequest {
+    TestRequest::patch()
+        .uri(uri)
+        .insert_header(("Content-Type", content_type))
+        .set_payload(body.to_string())
+}
+
+async fn server_with_billing() -> Server {
+    let server = Server::new(config(&[]), empty());
+    let billing = json!({
+        "name": "billing",
+        "type": "microservice",
+        "description": "Invoices",
+        "queue": {"publish_queues": ["invoices"]},
+        "metadata": {"team": "payments", "tier": "1"},
+    });
+    let app = test::init_service(server.app()).await;
+    let req = TestRequest::post().uri("/api/services").set_json(billing).to_request();
+    assert_eq!(call(&app, req).await.0, StatusCode::OK);
+    server
+}
+
+#[actix_web::test]
+async fn a_merge_patch_changes_only_what_it_names() {
+    let server = server_with_billing().await;
+    let app = test::init_service(server.app()).await;
+
+    let body = json!({"description": null, "metadata": {"tier": "2"}, "is_http_server": true});
+    let (status, svc) = call(&app, patch("/api/services/billing", "application/merge-patch+json", body).to_request()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(svc.get("description").is_none());
+    assert_eq!(svc["metadata"], json!({"team": "payments", "tier": "2"}));
+    assert_eq!(svc["is_http_server"], true);
+    assert_eq!(svc["queue"]["publish_queues"], json!(["invoices"]));
+
+    let stored = server.state.get_service("billing").unwrap();
+    assert_eq!(serde_json::to_value(stored).unwrap(), svc);
+}
+
+#[actix_web::test]
+async fn a_json_patch_applies_its_operations_in_order() {
+    let server = server_with_billing().await;
+    let app = test::init_service(server.app()).await;
+
+    let ops = json!([
+        {"op": "test", "path": "/queue/publish_queues/0", "value": "invoices"},
+        {"op": "add", "path": "/queue/publish_queues/-", "value": "refunds"},
+        {"op": "remove", "path": "/metadata/tier"},
+    ]);
+    let (status, svc) = call(&app, patch("/api/services/billing", "application/json-patch+json", ops).to_request()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(svc["queue"]["publish_queues"], json!(["invoices", "refunds"]));
+    assert_eq!(svc["metadata"], json!({"team": "payments"}));
+
+    // A failed `test` leaves the entity as it was.
+    let ops = json!([
+        {"op": "add", "path": "/queue/publish_queues/-", "value": "payouts"},
+        {"op": "test", "path": "/description", "value": "Something else"},
+    ]);
+    let (status, problem) = call(&app, patch("/api/services/billing", "application/json-patch+json", ops).to_request()).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(problem["code"], "invalid_patch");
+    assert_eq!(server.state.get_service("billing").unwrap().queue.unwrap().publish_queues.unwrap(), ["invoices", "refunds"]);
+}
+
+#[actix_web::test]
+async fn a_patch_is_validated_like_a_full_write() {
+    let server = server_with_billing().await;
+    let app = test::init_service(server.app()).await;
+
+    let (status, problem) = call(&app, patch("/api/services/billing", "application/merge-patch+json", json!({"type": "mainframe"})).to_request()).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(problem["code"], "validation_failed");
+    assert_eq!(problem["errors"][0]["path"], "/type");
+
+    let (status, problem) = call(&app, patch("/api/services/billing", "application/merge-patch+json", json!({"name": "invoicing"})).to_request()).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(problem["code"], "invalid_patch");
+
+    let (status, _) = call(&app, patch("/api/services/billing", "text/plain", json!({})).to_request()).await;
+    assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    let (status, _) = call(&app, patch("/api/services/nobody", "application/merge-patch+json", json!({})).to_request()).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(server.state.get_service("billing").unwrap().service_type, "microservice");
+}
+
+#[actix_web::test]
+async fn every_contract_kind_can_be_patched() {
+    let server = Server::new(config(&[]), empty());
+    let app = test::init_service(server.app()).await;
+    let contracts = [
+        ("/api/queue-contracts", queue_contract("orders"), "/api/queue-contracts/orders", "description"),
+        ("/api/nosql-contracts", json!({"entity_name": "Order", "description": "test"}), "/api/nosql-contracts/Order", "description"),
+        ("/api/proto-contracts", json!({"name": "Orders", "raw_proto": "syntax = \"proto3\";"}), "/api/proto-contracts/Orders", "raw_proto"),
+    ];
+    for (collection, contract, item, field) in contracts {
+        let req = TestRequest::post().uri(collection).set_json(contract).to_request();
+        assert_eq!(call(&app, req).await.0, StatusCode::OK, "{collection}");
+        let req = patch(item, "application/merge-patch+json", json!({ field: "patched" })).to_request();
+        let (status, patched) = call(&app, req).await;
+        assert_eq!(status, StatusCode::OK, "{item}: {patched}");
+        assert_eq!(patched[field], "patched", "{item}");
+    }
+}
@@ -0,0 +1,100 @@
+mod common;
+
+use std::collections::HashMap;
+
+use actix_web::http::StatusCode;
+use actix_web::test::{self, TestRequest};
+use codegang_datasource::config::Config;
+use common::*;
+use serde_json::json;
+
+/// A server with an `orders` queue and an `Orders` proto contract, both used
+/// by `billing`.
+async fn server_with_references(vars: &[(&str, &str)]) -> Server {
+    let server = Server::new(config(vars), empty());
+    let app = test::init_service(server.app()).await;
+    let writes = [
+        ("/api/queue-contracts", queue_contract("orders")),
+        ("/api/proto-contracts", json!({"name": "Orders", "raw_proto": "syntax = \"proto3\";"})),
+        ("/api/services", json!({
+            "name": "billing",
+            "type": "microservice",
+            "grpc_clients": ["Orders"],
+            "queue": {"subscribe_queues": ["orders"]},
+        })),
+    ];
+    for (uri, body) in writes {
+        let req = TestRequest::post().uri(uri).set_json(body).to_request();
+        assert_eq!(call(&app, req).await.0, StatusCode::OK, "{uri}");
+    }
+    server
+}
+
+fn delete(uri: &str) -> TestRequest {
+    TestRequest::delete().uri(uri)
+}
+
+#[actix_web::test]
+async fn deleting_a_referenced_contract_is_a_conflict() {
+    let server = server_with_references(&[]).await;
+    let app = test::init_service(server.app()).await;
+
+    for uri in ["/api/queue-contracts/orders", "/api/proto-contracts/Orders"] {
+        let (status, problem) = call(&app, delete(uri).to_request()).await;
+        assert_eq!(status, StatusCode::CONFLICT, "{uri}");
+        assert_eq!(problem["code"], "reference_conflict");
+        assert_eq!(problem["referenced_by"], json!(["billing"]));
+    }
+    let data = server.state.get_datasource();
+    assert_eq!(data.queue_contracts.len(), 1);
+    assert_eq!(data.proto_contracts.len(), 1);
+}
+
+#[actix_web::test]
+async fn cascade_strips_the_references() {
+    let server = server_with_references(&[]).await;
+    let app = test::init_service(server.app()).await;
+
+    let (status, _) = call(&app, delete("/api/queue-contracts/orders?cascade=true").to_request()).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = call(&app, delete("/api/proto-contracts/Orders?cascade=true").to_request()).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let billing = server.state.get_service("billing").unwrap();
+    assert_eq!(billing.queue.unwrap().subscribe_queues.unwrap(), Vec::<String>::new());
+    assert_eq!(billing.grpc_clients.unwrap(), Vec::<String>::new());
+    let data = server.state.get_datasource();
+    assert!(data.queue_contracts.is_empty());
+    assert!(data.proto_contracts.is_empty());
+}
+
+#[actix_web::test]
+async fn force_leaves_the_references_dangling() {
+    let server = server_with_references(&[]).await;
+    let app = test::init_service(server.app()).await;
+
+    let (status, _) = call(&app, delete("/api/queue-contracts/orders?force=true").to_request()).await;
+    assert_eq!(status, StatusCode::OK);
+
+    assert!(server.state.get_datasource().queue_contracts.is_empty());
+    let billing = server.state.get_service("billing").unwrap();
+    assert_eq!(billing.queue.unwrap().subscribe_queues.unwrap(), ["orders"]);
+}
+
+#[actix_web::test]
+async fn the_server_default_applies_without_flags() {
+    let server = server_with_references(&[("REFERENTIAL_INTEGRITY", "cascade")]).await;
+    let app = test::init_service(server.app()).await;
+
+    let (status, _) = call(&app, delete("/api/queue-contracts/orders").to_request()).await;
+    assert_eq!(status, StatusCode::OK);
+    let billing = server.state.get_service("billing").unwrap();
+    assert_eq!(billing.queue.unwrap().subscribe_queues.unwrap(), Vec::<String>::new());
+}
+
+#[test]
+fn an_unknown_default_is_a_configuration_error() {
+    let vars = HashMap::from([("REFERENTIAL_INTEGRITY", "cascde")]);
+    let err = Config::from_vars(|name| vars.get(name).map(|v| v.to_string())).err().expect("rejected");
+    assert!(err.to_string().starts_with("REFERENTIAL_INTEGRITY=cascde"), "{err}");
+}
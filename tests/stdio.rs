@@ -0,0 +1,128 @@
+mod common;
+
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use common::*;
+use serde_json::{json, Value};
+
+/// Runs the stdio binary in `dir` over `messages` and returns every line it
+/// wrote: responses and notifications.
+fn run_stdio(dir: &Path, args: &[&str], messages: &[Value]) -> Vec<Value> {
+    let data_file = dir.join("data.json");
+    let mut child = Command::new(env!("CARGO_BIN_EXE_codegang-datasource-mcp"))
+        .args(args)
+        .current_dir(dir)
+        .env("DATA_FILE", &data_file)
+        // Server settings that would fail to load, or fetch keys, if read.
+        .env("API_KEYS_FILE", dir.join("missing-keys.json"))
+        .env("JWT_JWKS_URL", "http://127.0.0.1:9/jwks.json")
+        .env_remove("CODEGANG_SERVER_URL")
+        .env_remove("AUDIT_LOG_FILE")
+        .env_remove("WEBHOOKS_FILE")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    for message in messages {
+        writeln!(stdin, "{message}").unwrap();
+    }
+    drop(stdin);
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+
+    String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+fn initialize() -> Value {
+    json!({"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {"protocolVersion": "2025-06-18", "capabilities": {}, "clientInfo": {"name": "test", "version": "1"}}})
+}
+
+fn methods(lines: &[Value]) -> Vec<&str> {
+    lines.iter().filter_map(|line| line["method"].as_str()).collect()
+}
+
+#[test]
+fn the_stdio_server_needs_only_the_data_file() {
+    let dir = temp_path("stdio");
+    std::fs::create_dir(&dir).unwrap();
+    let data_file = dir.join("data.json");
+
+    let messages = [
+        initialize(),
+        json!({"jsonrpc": "2.0", "id": 2, "method": "tools/call", "params": {"name": "upsert_queue_contract", "arguments": queue_contract("orders")}}),
+    ];
+    let lines = run_stdio(&dir, &["--allow-writes"], &messages);
+    let responses: Vec<&Value> = lines.iter().filter(|line| line.get("id").is_some()).collect();
+    assert_eq!(responses.len(), 2);
+    assert_eq!(responses[1]["result"]["isError"], false, "{}", responses[1]);
+
+    let data: Value = serde_json::from_str(&std::fs::read_to_string(&data_file).unwrap()).unwrap();
+    assert_eq!(data["queue_contracts"][0]["topic_name"], "orders");
+    let files: Vec<_> = std::fs::read_dir(&dir).unwrap().map(|entry| entry.unwrap().file_name()).collect();
+    assert_eq!(files, ["data.json"]);
+}
+
+#[test]
+fn notifications_follow_the_response_that_caused_them() {
+    let dir = temp_path("stdio-notifications");
+    std::fs::create_dir(&dir).unwrap();
+
+    let messages = [
+        initialize(),
+        json!({"jsonrpc": "2.0", "id": 2, "method": "logging/setLevel", "params": {"level": "info"}}),
+        json!({"jsonrpc": "2.0", "id": 3, "method": "resources/subscribe", "params": {"uri": "codegang://services/api"}}),
+        json!({"jsonrpc": "2.0", "id": 4, "method": "tools/call", "params": {"name": "upsert_service", "arguments": service("api", &["team-a"])}}),
+        json!({"jsonrpc": "2.0", "id": 5, "method": "tools/call", "params": {"name": "select_workspace", "arguments": {"workspace": "team-a"}}}),
+    ];
+    let lines = run_stdio(&dir, &["--allow-writes"], &messages);
+
+    let capabilities = &lines[0]["result"]["capabilities"];
+    assert_eq!(capabilities["resources"]["subscribe"], true);
+    assert_eq!(lines[3]["id"], 4, "{lines:?}");
+    assert_eq!(
+        methods(&lines[4..]),
+        [
+            "notifications/resources/updated",
+            "notifications/resources/list_changed",
+            "notifications/message",
+            "notifications/tools/list_changed"
+        ]
+    );
+    assert_eq!(lines[4]["params"]["uri"], "codegang://services/api");
+    assert_eq!(lines[6]["params"]["data"]["change"]["action"], "created");
+    assert_eq!(lines[7]["id"], 5);
+    assert_eq!(lines[8]["method"], "notifications/tools/list_changed");
+}
+
+#[test]
+fn server_mode_asks_the_server_only_for_registry_data_and_valid_writes() {
+    let dir = temp_path("stdio-server");
+    std::fs::create_dir(&dir).unwrap();
+
+    // Nothing listens there: any request to the server fails.
+    let args = ["--server-url", "http://127.0.0.1:9", "--allow-writes"];
+    let messages = [
+        initialize(),
+        json!({"jsonrpc": "2.0", "method": "notifications/initialized"}),
+        json!({"jsonrpc": "2.0", "id": 2, "method": "ping"}),
+        json!({"jsonrpc": "2.0", "id": 3, "method": "tools/call", "params": {"name": "delete_service", "arguments": {}}}),
+        json!({"jsonrpc": "2.0", "id": 4, "method": "tools/call", "params": {"name": "list_services", "arguments": {}}}),
+    ];
+    let lines = run_stdio(&dir, &args, &messages);
+    assert_eq!(lines.len(), 4, "{lines:?}");
+
+    assert_eq!(lines[0]["result"]["capabilities"]["resources"]["subscribe"], false);
+    assert_eq!(lines[1]["result"], json!({}));
+    assert_eq!(lines[2]["result"]["isError"], true);
+    let message = lines[2]["result"]["content"][0]["text"].as_str().unwrap();
+    assert!(message.starts_with("Invalid arguments for delete_service"), "{message}");
+    assert_eq!(lines[3]["error"]["code"], -32603);
+}
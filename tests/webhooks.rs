@@ -0,0 +1,202 @@
+mod common;
+
+use std::collections::{HashMap, VecDeque};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use actix_web::http::StatusCode;
+use actix_web::test::{self, TestRequest};
+use codegang_datasource::config::Config;
+use codegang_datasource::model::QueueContract;
+use codegang_datasource::webhooks::{self, sign};
+use common::*;
+use serde_json::{json, Value};
+
+/// A request the stand-in receiver got.
+struct Received {
+    at: Instant,
+    headers: HashMap<String, String>,
+    body: String,
+}
+
+/// A local webhook receiver that answers with the queued statuses, then
+/// with 200.
+#[derive(Clone)]
+struct Receiver {
+    url: String,
+    received: Arc<Mutex<Vec<Received>>>,
+}
+
+impl Receiver {
+    fn start(statuses: &[u16]) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let mut statuses: VecDeque<u16> = statuses.iter().copied().collect();
+        let log = Arc::clone(&received);
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { continue };
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut headers = HashMap::new();
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                loop {
+                    line.clear();
+                    reader.read_line(&mut line).unwrap();
+                    let Some((name, value)) = line.trim_end().split_once(": ") else { break };
+                    headers.insert(name.to_lowercase(), value.to_string());
+                }
+                let length = headers.get("content-length").and_then(|l| l.parse().ok()).unwrap_or(0);
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                log.lock().unwrap().push(Received {
+                    at: Instant::now(),
+                    headers,
+                    body: String::from_utf8(body).unwrap(),
+                });
+                let status = statuses.pop_front().unwrap_or(200);
+                let response = format!("HTTP/1.1 {status} Status\r\nContent-Length: 0\r\nConnection: close\r\n\r\n");
+                let _ = stream.write_all(response.as_bytes());
+            }
+        });
+        Self { url, received }
+    }
+
+    /// Waits until `count` requests have arrived.
+    async fn wait_for(&self, count: usize) {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while self.received.lock().unwrap().len() < count {
+            assert!(Instant::now() < deadline, "only {} requests arrived", self.received.lock().unwrap().len());
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    }
+}
+
+fn contract(topic: &str) -> QueueContract {
+    serde_json::from_value(queue_contract(topic)).unwrap()
+}
+
+#[actix_web::test]
+async fn deliveries_are_signed_and_retried_with_backoff() {
+    let receiver = Receiver::start(&[500, 503]);
+    let server = Server::new(config(&[("WEBHOOK_MAX_ATTEMPTS", "3"), ("WEBHOOK_RETRY_BASE_MS", "100")]), empty());
+    webhooks::spawn_dispatcher(server.state.clone(), server.webhooks.clone());
+    let app = test::init_service(server.app()).await;
+
+    let req = TestRequest::post().uri("/api/webhooks").set_json(json!({"url": receiver.url, "kinds": ["queue_contract"]})).to_request();
+    let (status, hook) = call(&app, req).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let secret = hook["secret"].as_str().unwrap();
+
+    let req = TestRequest::post().uri("/api/queue-contracts").set_json(queue_contract("orders")).to_request();
+    call(&app, req).await;
+    receiver.wait_for(3).await;
+
+    {
+        let received = receiver.received.lock().unwrap();
+        for request in received.iter() {
+            assert_eq!(request.headers["x-codegang-signature"], sign(secret, &request.body));
+            assert_eq!(request.headers["x-codegang-event"], "queue_contract.created");
+            assert_eq!(request.headers["x-codegang-delivery"], received[0].headers["x-codegang-delivery"]);
+            let body: Value = serde_json::from_str(&request.body).unwrap();
+            assert_eq!((body["key"].as_str(), body["revision"].as_u64()), (Some("orders"), Some(1)));
+        }
+        assert!(received[1].at - received[0].at >= Duration::from_millis(100));
+        assert!(received[2].at - received[1].at >= Duration::from_millis(200));
+    }
+
+    let uri = format!("/api/webhooks/{}/deliveries", hook["id"].as_str().unwrap());
+    let (_, deliveries) = call(&app, TestRequest::get().uri(&uri).to_request()).await;
+    assert_eq!(deliveries[0]["status"], "succeeded");
+    let codes: Vec<&Value> = deliveries[0]["attempts"].as_array().unwrap().iter().map(|a| &a["status_code"]).collect();
+    assert_eq!(codes, [&json!(500), &json!(503), &json!(200)]);
+}
+
+#[actix_web::test]
+async fn a_delivery_is_failed_after_the_last_attempt() {
+    let receiver = Receiver::start(&[500, 500, 500]);
+    let server = Server::new(config(&[("WEBHOOK_MAX_ATTEMPTS", "2"), ("WEBHOOK_RETRY_BASE_MS", "10")]), empty());
+    webhooks::spawn_dispatcher(server.state.clone(), server.webhooks.clone());
+    let app = test::init_service(server.app()).await;
+
+    let req = TestRequest::post().uri("/api/webhooks").set_json(json!({"url": receiver.url})).to_request();
+    let (_, hook) = call(&app, req).await;
+    let req = TestRequest::post().uri("/api/queue-contracts").set_json(queue_contract("orders")).to_request();
+    call(&app, req).await;
+    receiver.wait_for(2).await;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let uri = format!("/api/webhooks/{}/deliveries", hook["id"].as_str().unwrap());
+    let (_, deliveries) = call(&app, TestRequest::get().uri(&uri).to_request()).await;
+    assert_eq!(deliveries[0]["status"], "failed");
+    assert_eq!(deliveries[0]["attempts"].as_array().unwrap().len(), 2);
+    assert_eq!(receiver.received.lock().unwrap().len(), 2);
+}
+
+#[actix_web::test]
+async fn a_dispatcher_that_falls_behind_catches_up() {
+    let receiver = Receiver::start(&[]);
+    let server = Server::new(config(&[]), empty());
+    server.webhooks.create(serde_json::from_value(json!({"url": receiver.url, "keys": ["first"]})).unwrap());
+    webhooks::spawn_dispatcher(server.state.clone(), server.webhooks.clone());
+
+    // More changes than the broadcast holds, before the dispatcher runs.
+    for topic in std::iter::once("first".to_string()).chain((0..300).map(|i| format!("topic-{i}"))) {
+        assert!(server.state.upsert_queue_contract(contract(&topic), &actor()).is_ok());
+    }
+    receiver.wait_for(1).await;
+    let body: Value = serde_json::from_str(&receiver.received.lock().unwrap()[0].body).unwrap();
+    assert_eq!(body["key"], "first");
+}
+
+#[actix_web::test]
+async fn changes_lost_before_delivery_are_recorded_as_failed() {
+    let server = Server::new(config(&[]), empty());
+    let hook = server.webhooks.create(serde_json::from_value(json!({"url": "http://127.0.0.1:9/", "keys": ["none"]})).unwrap());
+    webhooks::spawn_dispatcher(server.state.clone(), server.webhooks.clone());
+
+    // More changes than the change log keeps.
+    for i in 0..1100 {
+        assert!(server.state.upsert_queue_contract(contract(&format!("topic-{i}")), &actor()).is_ok());
+    }
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let missed = loop {
+        if let Some(missed) = server.webhooks.deliveries(&hook.id).into_iter().next() {
+            break missed;
+        }
+        assert!(Instant::now() < deadline, "no delivery was recorded");
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    };
+    let missed = serde_json::to_value(missed).unwrap();
+    assert_eq!(missed["event"], "missed");
+    assert_eq!(missed["status"], "failed");
+    assert_eq!(missed["error"], "Changes 1 to 1100 were dropped before they could be delivered");
+}
+
+#[test]
+fn subscriptions_persist_beside_the_data_file_readable_only_by_the_owner() {
+    let dir = temp_path("webhooks-file");
+    std::fs::create_dir(&dir).unwrap();
+    let data_file = dir.join("data.json").display().to_string();
+    let config = Config::from_vars(|name| match name {
+        "DATA_FILE" => Some(data_file.clone()),
+        "AUDIT_LOG_FILE" => Some(String::new()),
+        _ => None,
+    })
+    .unwrap();
+    let file = dir.join("codegang-webhooks.json");
+    assert_eq!(config.webhooks_file.as_deref(), Some(file.to_str().unwrap()));
+
+    let hooks = webhooks::Webhooks::new(&config).unwrap();
+    hooks.create(serde_json::from_value(json!({"url": "http://127.0.0.1:9/hook"})).unwrap());
+    let saved: Value = serde_json::from_str(&std::fs::read_to_string(&file).unwrap()).unwrap();
+    assert!(saved[0]["secret"].is_string());
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        assert_eq!(std::fs::metadata(&file).unwrap().permissions().mode() & 0o777, 0o600);
+    }
+}